@@ -1,70 +1,518 @@
-use std::env;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
-use phonetics_to_hangul::{arpabet, ipa_to_hangul, word_lookup};
-use structopt::StructOpt;
+use anyhow::{bail, Context, Result};
+use phonetics_to_hangul::{
+    accent::Accent,
+    arpabet,
+    batch::{self, Outcome},
+    evaluation, fidelity, ipa_to_hangul, ipa_to_katakana,
+    lookup_cache::{self, Cache, CachedSource},
+    pronunciation_source::{Pronunciation, Resolutions, Resolver},
+    romanization,
+    user_dictionary::UserDictionary,
+    wiktionary, word_lookup,
+};
+use structopt::{clap::AppSettings, StructOpt};
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Source {
+    User,
+    Cmudict,
+    Wiktionary,
+    Online,
+}
+
+impl FromStr for Source {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "user" => Source::User,
+            "cmudict" => Source::Cmudict,
+            "wiktionary" => Source::Wiktionary,
+            "online" => Source::Online,
+            _ => bail!("Unknown pronunciation source `{}`.", s),
+        })
+    }
+}
 
 /// Turns a word's pronunciation into 한글 with pronunciation as close as
 /// possible to the original word.
 #[derive(StructOpt)]
-#[structopt(name = "phonetics-to-hangul")]
+#[structopt(
+    name = "phonetics-to-hangul",
+    setting = AppSettings::SubcommandsNegateReqs
+)]
 struct Opt {
-    /// The language of the word. Only used when the online dictionary is in use.
+    /// The language of the word. CMUdict only covers English.
     #[structopt(short, long, default_value = "en")]
     lang: String,
     /// Switch to an online dictionary instead.
     #[structopt(short, long)]
     online: bool,
+    /// The sources to look up the pronunciation in, in the order they are
+    /// tried: `user`, `cmudict`, `wiktionary` and `online`. Defaults to
+    /// `user,cmudict,wiktionary`, or `online` when switching to the online
+    /// dictionary.
+    #[structopt(short, long, use_delimiter = true)]
+    sources: Vec<Source>,
+    /// A file with additional pronunciations, one word and its pronunciation
+    /// in IPA per line, separated by a tab.
+    #[structopt(short = "d", long, parse(from_os_str))]
+    user_dictionary: Option<PathBuf>,
+    /// A Wiktionary extract from kaikki.org in the JSON Lines format, to look
+    /// up the pronunciations of words in many languages offline.
+    #[structopt(short, long, parse(from_os_str))]
+    wiktionary: Option<PathBuf>,
+    /// Show all the pronunciations the source knows, such as the ones for
+    /// different parts of speech, instead of only the first.
+    #[structopt(short, long)]
+    all: bool,
+    /// Additionally list up to this many alternative transcriptions, ranked
+    /// by how faithfully they reproduce the pronunciation.
+    #[structopt(short, long)]
+    candidates: Option<usize>,
+    /// Transcribe into the candidate that reproduces the pronunciation most
+    /// faithfully, instead of following the preferred rules.
+    #[structopt(long)]
+    best: bool,
+    /// Show which rule produced each part of the 한글 and how the syllables
+    /// were assembled.
+    #[structopt(short, long)]
+    explain: bool,
+    /// The accent of English to transcribe: `us` or `uk`. For the British
+    /// accent, the pronunciations from CMUdict are turned non-rhotic and
+    /// British pronunciations from Wiktionary are preferred.
+    #[structopt(long, default_value = "us")]
+    accent: Accent,
+    /// The romanization system to show the 한글 in: `rr` (Revised
+    /// Romanization), `mr` (McCune–Reischauer) or `yale`.
+    #[structopt(short, long, default_value = "rr")]
+    romanization: romanization::System,
+    /// The file online lookups are cached in. Defaults to a file in the
+    /// user's cache directory.
+    #[structopt(long, parse(from_os_str))]
+    cache: Option<PathBuf>,
+    /// How many days cached online lookups stay valid.
+    #[structopt(long, default_value = "30")]
+    cache_ttl: u64,
+    /// Look up words online again, even if they are cached.
+    #[structopt(long)]
+    refresh: bool,
+    /// Serve online lookups only from the cache, without accessing the
+    /// online dictionary.
+    #[structopt(long, conflicts_with = "refresh")]
+    cache_only: bool,
+    /// Look up all the words in the file, one per line, in the online
+    /// dictionary to fill the cache, instead of transcribing a word.
+    #[structopt(long, parse(from_os_str))]
+    warm_cache: Option<PathBuf>,
+    /// Transcribe all the words in the file, one per line, instead of a
+    /// single word. Each transcription is printed as a line of tab separated
+    /// values: the word, the source, the pronunciation and the 한글. Words
+    /// that need the online dictionary are looked up concurrently.
+    #[structopt(short, long, parse(from_os_str))]
+    batch: Option<PathBuf>,
     /// The word to replicate the pronunciation of in 한글.
-    word: String,
+    #[structopt(required_unless_one = &["warm-cache", "batch"])]
+    word: Option<String>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-fn try_run() -> Result<()> {
-    let opt: Opt = StructOpt::from_args();
+#[derive(StructOpt)]
+enum Command {
+    /// Transcribe a corpus of English loanwords and compare the results to
+    /// their accepted spellings. The pronunciations are looked up in the
+    /// offline sources.
+    Eval {
+        /// A corpus to use instead of the bundled one, with one word and its
+        /// spelling in 한글 per line, separated by a tab.
+        #[structopt(parse(from_os_str))]
+        corpus: Option<PathBuf>,
+        /// List every word that was transcribed wrongly.
+        #[structopt(short, long)]
+        verbose: bool,
+    },
+}
+
+fn options(opt: &Opt) -> ipa_to_hangul::Options {
+    ipa_to_hangul::Options { accent: opt.accent }
+}
+
+fn print_transcription(pronunciation: &Pronunciation, source: &str, opt: &Opt) {
+    let ipa = &pronunciation.ipa;
+    let best = if opt.best {
+        ipa_to_hangul::candidates_with(ipa.chars(), 1, options(opt)).pop()
+    } else {
+        None
+    };
+    let hangul = match &best {
+        Some(candidate) => candidate.hangul.clone(),
+        None => ipa_to_hangul::convert_with(&mut Default::default(), ipa.chars(), options(opt))
+            .collect(),
+    };
+
+    println!("Word: {}", pronunciation.word);
+    if let Some(part_of_speech) = &pronunciation.part_of_speech {
+        println!("Part of speech: {}", part_of_speech);
+    }
+    println!("Pronunciation: {}", ipa);
+    println!("Source: {}", source);
+    println!("한글: {}", hangul);
+    println!(
+        "カタカナ: {}",
+        ipa_to_katakana::convert(&mut Default::default(), ipa.chars())
+    );
+    println!(
+        "Romanization: {}",
+        romanization::romanize(&hangul, opt.romanization)
+    );
+    println!("Fidelity: {:.0}%", 100.0 * fidelity::score(ipa, &hangul));
+
+    if opt.explain {
+        println!("Explanation:");
+        // Explain the candidate that was picked, not the preferred rules.
+        let mut builder = Default::default();
+        let steps = match &best {
+            Some(candidate) => {
+                ipa_to_hangul::trace_candidate(&mut builder, ipa.chars(), options(opt), candidate)
+            }
+            None => ipa_to_hangul::trace(&mut builder, ipa.chars(), options(opt)),
+        };
+        for step in steps {
+            println!("  {}", step);
+        }
+    }
 
-    if !opt.online {
-        let dictionary = arpabet::Dictionary::parse(arpabet::CMUDICT_07B)
-            .context("Failed parsing the dictionary.")?;
+    if let Some(n) = opt.candidates {
+        println!("Candidates:");
+        for candidate in ipa_to_hangul::candidates_with(ipa.chars(), n, options(opt)) {
+            println!("  {} ({:.0}%)", candidate.hangul, 100.0 * candidate.score);
+        }
+    }
+}
+
+fn client_builder() -> Result<word_lookup::ClientBuilder> {
+    let user = env::var("DICT_USER").context(
+        "For online usage, you need to provide the \
+        user name via the `DICT_USER` environment variable.",
+    )?;
+
+    let pass = env::var("DICT_PASS").context(
+        "For online usage, you need to provide the \
+        user's password via the `DICT_PASS` environment variable.",
+    )?;
+
+    let mut client = word_lookup::Client::builder(user, pass);
+    if let Ok(url) = env::var("DICT_URL") {
+        client = client.base_url(url);
+    }
+    Ok(client)
+}
+
+fn open_cache(opt: &Opt) -> Result<Cache> {
+    let path = match &opt.cache {
+        Some(path) => path.clone(),
+        None => dirs::cache_dir()
+            .context("There is no cache directory, specify a cache file instead.")?
+            .join("phonetics-to-hangul")
+            .join("lookups.tsv"),
+    };
+    Ok(Cache::open(path)?.with_ttl(Duration::from_secs(opt.cache_ttl * 24 * 60 * 60)))
+}
+
+fn online_source(opt: &Opt) -> Result<CachedSource<word_lookup::Client>> {
+    let cache = open_cache(opt)?;
 
-        let pronunciation = dictionary
-            .look_up(&opt.word)
-            .context("The word is not in the dictionary.")?;
+    Ok(if opt.cache_only {
+        CachedSource::cache_only(cache)
+    } else {
+        let mode = if opt.refresh {
+            lookup_cache::Mode::Refresh
+        } else {
+            lookup_cache::Mode::Normal
+        };
+        CachedSource::new(client_builder()?.build()?, cache, mode)
+    })
+}
+
+/// Looks up the words online concurrently and caches the results.
+fn fetch_online(
+    words: Vec<String>,
+    cache: &mut Cache,
+    language: &str,
+) -> Result<Vec<(String, Result<Vec<Pronunciation>>)>> {
+    let client = client_builder()?.build_async()?;
+    let mut runtime = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .context("Failed starting the async runtime.")?;
 
-        println!("Word: {}", opt.word);
+    let results = runtime.block_on(client.lookup_batch(words, language));
 
-        print!("Pronunciation: ");
-        for c in pronunciation.clone() {
-            print!("{}", c);
+    Ok(results
+        .into_iter()
+        .map(|(word, result)| {
+            let result = result.map(|words| {
+                let pronunciations = words.into_iter().map(Into::into).collect::<Vec<_>>();
+                cache.insert(&word, language, pronunciations.clone(), true);
+                pronunciations
+            });
+            (word, result)
+        })
+        .collect())
+}
+
+fn read_word_list(path: &Path) -> Result<Vec<String>> {
+    let words = fs::read_to_string(path).context("Failed reading the word list.")?;
+    Ok(batch::read_word_list(&words))
+}
+
+fn warm_cache(path: &Path, opt: &Opt) -> Result<()> {
+    if opt.cache_only {
+        bail!("The cache can't be filled without accessing the online dictionary.");
+    }
+
+    let mut cache = open_cache(opt)?;
+    let words = read_word_list(path)?
+        .into_iter()
+        .filter(|word| opt.refresh || cache.get(word, &opt.lang, true).is_none())
+        .collect();
+
+    for (word, result) in fetch_online(words, &mut cache, &opt.lang)? {
+        match result {
+            Ok(pronunciations) if pronunciations.is_empty() => println!("{}: not found", word),
+            Ok(pronunciations) => {
+                let ipa = pronunciations.iter().map(|p| &*p.ipa).collect::<Vec<_>>();
+                println!("{}: {}", word, ipa.join(", "));
+            }
+            Err(e) => println!("{}: {:#}", word, e),
+        }
+    }
+
+    cache.save().context("Failed saving the lookup cache.")
+}
+
+fn build_resolver<'a>(
+    sources: &[Source],
+    online: Option<&'a CachedSource<word_lookup::Client>>,
+    opt: &Opt,
+) -> Result<Resolver<'a>> {
+    let mut resolver = Resolver::new();
+    for source in sources {
+        match source {
+            Source::User => {
+                // Without a user dictionary, there's nothing to look up.
+                if let Some(path) = &opt.user_dictionary {
+                    let txt =
+                        fs::read_to_string(path).context("Failed reading the user dictionary.")?;
+                    resolver.push(
+                        UserDictionary::parse(&txt)
+                            .context("Failed parsing the user dictionary.")?,
+                    );
+                }
+            }
+            Source::Cmudict => resolver.push(
+                arpabet::Dictionary::parse(arpabet::CMUDICT_07B)
+                    .context("Failed parsing the dictionary.")?
+                    .with_accent(opt.accent),
+            ),
+            Source::Wiktionary => {
+                if let Some(path) = &opt.wiktionary {
+                    let file =
+                        fs::File::open(path).context("Failed opening the Wiktionary extract.")?;
+                    resolver.push(
+                        wiktionary::Dictionary::read(io::BufReader::new(file))?
+                            .with_accent(opt.accent),
+                    );
+                }
+            }
+            Source::Online => {
+                if let Some(online) = online {
+                    resolver.push(online);
+                }
+            }
+        }
+    }
+    Ok(resolver)
+}
+
+/// Transcribes all the words in the file, one per line. The words are looked
+/// up in the offline sources first. The remaining ones are then looked up in
+/// the online dictionary concurrently.
+fn run_batch(path: &Path, sources: &[Source], opt: &Opt) -> Result<()> {
+    let words = read_word_list(path)?;
+    let resolver = build_resolver(sources, None, opt)?;
+    let mut cache = if sources.contains(&Source::Online) {
+        Some(open_cache(opt)?)
+    } else {
+        None
+    };
+
+    let mut outcomes = batch::resolve(&resolver, &words, &opt.lang);
+    let mut missing = Vec::new();
+
+    if let Some(cache) = &mut cache {
+        for (i, (word, outcome)) in words.iter().zip(&mut outcomes).enumerate() {
+            if !matches!(outcome, Outcome::NotFound) {
+                continue;
+            }
+            let cached = if opt.refresh {
+                None
+            } else if opt.cache_only {
+                cache.get_expired(word, &opt.lang, opt.all)
+            } else {
+                cache.get(word, &opt.lang, opt.all)
+            };
+            match cached {
+                Some([]) => {}
+                Some(pronunciations) => {
+                    *outcome = Outcome::Found {
+                        source: "cache".to_owned(),
+                        pronunciations: pronunciations.to_vec(),
+                    }
+                }
+                None if !opt.cache_only => missing.push(i),
+                None => {}
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing_words = missing.iter().map(|&i| words[i].clone()).collect();
+            let results = fetch_online(missing_words, cache, &opt.lang)?;
+            for (&i, (_, result)) in missing.iter().zip(results) {
+                outcomes[i] = match result {
+                    Ok(pronunciations) if pronunciations.is_empty() => Outcome::NotFound,
+                    Ok(pronunciations) => Outcome::Found {
+                        source: "Lexicala".to_owned(),
+                        pronunciations,
+                    },
+                    Err(e) => Outcome::Failed(e),
+                };
+            }
+        }
+        cache.save().context("Failed saving the lookup cache.")?;
+    }
+
+    for (word, outcome) in words.iter().zip(&outcomes) {
+        match outcome {
+            Outcome::Found { .. } => {
+                for mut transcription in outcome.transcriptions(opt.all, options(opt)) {
+                    if opt.best {
+                        transcription.hangul =
+                            ipa_to_hangul::convert_best(transcription.ipa.chars(), options(opt));
+                    }
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        word, transcription.source, transcription.ipa, transcription.hangul
+                    );
+                }
+            }
+            Outcome::NotFound => eprintln!("{}: not found", word),
+            Outcome::Failed(e) => eprintln!("{}: {:#}", word, e),
+        }
+    }
+
+    Ok(())
+}
+
+fn run_evaluation(
+    corpus: Option<&Path>,
+    verbose: bool,
+    sources: &[Source],
+    opt: &Opt,
+) -> Result<()> {
+    let corpus = match corpus {
+        Some(path) => {
+            let txt = fs::read_to_string(path).context("Failed reading the corpus.")?;
+            evaluation::parse_corpus(&txt)?
+        }
+        None => evaluation::parse_corpus(evaluation::LOANWORDS)?,
+    };
+    let resolver = build_resolver(sources, None, opt)?;
+
+    let report = evaluation::evaluate(&corpus, options(opt), |word| {
+        resolver
+            .resolve(word, &opt.lang)
+            .ok()
+            .map(|resolution| resolution.pronunciation.ipa)
+    });
+
+    if verbose {
+        for outcome in report.outcomes.iter().filter(|o| !o.is_correct()) {
+            println!(
+                "{}\t{}\t{}\t{}",
+                outcome.word, outcome.ipa, outcome.expected, outcome.actual
+            );
         }
         println!();
+    }
+    print!("{}", report);
 
-        println!(
-            "한글: {}",
-            ipa_to_hangul::convert(&mut Default::default(), pronunciation).collect::<String>()
-        );
+    Ok(())
+}
+
+fn try_run() -> Result<()> {
+    let opt: Opt = StructOpt::from_args();
+
+    if let Some(path) = &opt.warm_cache {
+        return warm_cache(path, &opt);
+    }
+
+    let sources = if !opt.sources.is_empty() {
+        opt.sources.clone()
+    } else if opt.online {
+        vec![Source::Online]
+    } else {
+        vec![Source::User, Source::Cmudict, Source::Wiktionary]
+    };
+
+    if let Some(Command::Eval { corpus, verbose }) = &opt.command {
+        return run_evaluation(corpus.as_deref(), *verbose, &sources, &opt);
+    }
+
+    if let Some(path) = &opt.batch {
+        return run_batch(path, &sources, &opt);
+    }
+    let word = opt.word.as_deref().unwrap_or_default();
+
+    let online = if sources.contains(&Source::Online) {
+        Some(online_source(&opt)?)
     } else {
-        let user = env::var("DICT_USER").context(
-            "For online usage, you need to provide the \
-            user name via the `DICT_USER` environment variable.",
-        )?;
+        None
+    };
 
-        let pass = env::var("DICT_PASS").context(
-            "For online usage, you need to provide the \
-            user's password via the `DICT_PASS` environment variable.",
-        )?;
+    let resolver = build_resolver(&sources, online.as_ref(), &opt)?;
 
-        let client = word_lookup::Client::new(user, pass);
+    let resolutions = if opt.all {
+        resolver.resolve_all(word, &opt.lang)
+    } else {
+        resolver
+            .resolve(word, &opt.lang)
+            .map(|resolution| Resolutions {
+                source: resolution.source,
+                pronunciations: vec![resolution.pronunciation],
+            })
+    };
 
-        let word = client
-            .lookup(&opt.word, &opt.lang)
-            .context("Failed looking up the word.")?;
+    if let Some(online) = &online {
+        online.save().context("Failed saving the lookup cache.")?;
+    }
 
-        let hangul = ipa_to_hangul::convert(&mut Default::default(), word.pronunciation.chars())
-            .collect::<String>();
+    let resolutions = resolutions.context("Failed looking up the word.")?;
 
-        println!("Word: {}", word.word);
-        println!("Pronunciation: {}", word.pronunciation);
-        println!("한글: {}", hangul);
+    for (i, pronunciation) in resolutions.pronunciations.iter().enumerate() {
+        if i != 0 {
+            println!();
+        }
+        print_transcription(pronunciation, resolutions.source, &opt);
     }
 
     Ok(())
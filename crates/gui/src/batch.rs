@@ -0,0 +1,246 @@
+use std::{cell::RefCell, collections::HashMap, fs, rc::Rc};
+
+use anyhow::{Context, Result};
+use iui::{
+    controls::{Button, Checkbox, HorizontalBox, Label, MultilineEntry, ProgressBar, VerticalBox},
+    prelude::*,
+};
+use phonetics_to_hangul::{
+    batch::{self, Transcription},
+    document::{self, Document, Format},
+    pronunciation_source::Resolver,
+    user_dictionary::UserDictionary,
+};
+use LayoutStrategy::{Compact, Stretchy};
+
+use crate::State;
+
+/// How many words are looked up before the progress is shown.
+const CHUNK_SIZE: usize = 200;
+
+/// The number of rows the preview shows.
+const PREVIEW_ROWS: usize = 50;
+
+/// A file that is being transcribed.
+struct Job {
+    document: Document,
+    words: Vec<String>,
+    /// The same pipeline as the CLI's batch mode, with the user's corrections
+    /// taking precedence over the dictionary.
+    resolver: Resolver<'static>,
+    /// The user's corrections when the job was started.
+    overrides: Rc<UserDictionary>,
+    /// The transcriptions of the words looked up so far, by their lowercase
+    /// form.
+    transcriptions: HashMap<String, Transcription>,
+    looked_up: usize,
+}
+
+struct BatchWindow {
+    ui: UI,
+    win: Window,
+    progress: ProgressBar,
+    status: Label,
+    preview: MultilineEntry,
+    header: Checkbox,
+    job: Option<Job>,
+    /// The rows of the finished file, with the pronunciation and the 한글
+    /// added.
+    rows: Vec<Vec<String>>,
+}
+
+impl BatchWindow {
+    fn open_file(&mut self, state: &State) -> Result<bool> {
+        let path = match self.win.open_file(&self.ui) {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+        let txt = fs::read_to_string(&path).context("Failed reading the file.")?;
+        let format = Format::from_path(&path);
+        let header = format == Format::Csv && self.header.checked(&self.ui);
+        let document = Document::parse(format, &txt)?.with_header(header);
+        let words = batch::words(document.texts());
+
+        let overrides = Rc::new(state.overrides.clone());
+        let mut resolver = Resolver::new();
+        resolver.push(overrides.clone());
+        resolver.push(state.dictionary.clone());
+
+        self.rows.clear();
+        self.preview.set_value(&self.ui, "");
+        self.status
+            .set_text(&self.ui, &format!("Looking up {} words…", words.len()));
+        self.job = Some(Job {
+            document,
+            words,
+            resolver,
+            overrides,
+            transcriptions: HashMap::new(),
+            looked_up: 0,
+        });
+        Ok(true)
+    }
+
+    /// Looks up the next words of the job. Returns `true` once the job is
+    /// finished.
+    fn process_chunk(&mut self) -> bool {
+        let job = match &mut self.job {
+            Some(job) => job,
+            None => return true,
+        };
+
+        let end = (job.looked_up + CHUNK_SIZE).min(job.words.len());
+        let words = &job.words[job.looked_up..end];
+        for (word, outcome) in words.iter().zip(batch::resolve(&job.resolver, words, "en")) {
+            if let Some(mut transcription) = outcome.transcriptions(false, Default::default()).pop()
+            {
+                if let Some(hangul) = job.overrides.hangul(word) {
+                    transcription.hangul = hangul.to_owned();
+                }
+                job.transcriptions
+                    .insert(word.to_lowercase(), transcription);
+            }
+        }
+        job.looked_up = end;
+
+        if job.looked_up < job.words.len() {
+            let percent = 100 * job.looked_up / job.words.len();
+            self.progress.set_value(&self.ui, percent as u32);
+            return false;
+        }
+
+        let job = match self.job.take() {
+            Some(job) => job,
+            None => return true,
+        };
+        self.rows = job.document.transcribe(|text| {
+            batch::transcribe_text(text, |word| job.transcriptions.get(&word.to_lowercase()))
+        });
+
+        let found = job.transcriptions.len();
+        self.progress.set_value(&self.ui, 100);
+        self.status.set_text(
+            &self.ui,
+            &format!(
+                "Transcribed {} rows. {} of {} words were found.",
+                self.rows.len(),
+                found,
+                job.words.len()
+            ),
+        );
+        let preview = &self.rows[..self.rows.len().min(PREVIEW_ROWS)];
+        self.preview
+            .set_value(&self.ui, &document::to_text(preview));
+        true
+    }
+
+    fn save(&self, to_string: fn(&[Vec<String>]) -> String) {
+        if self.job.is_some() || self.rows.is_empty() {
+            return;
+        }
+        if let Some(path) = self.win.save_file(&self.ui) {
+            if let Err(e) = fs::write(&path, to_string(&self.rows)) {
+                self.win
+                    .modal_err(&self.ui, "Failed saving", &e.to_string());
+            }
+        }
+    }
+}
+
+/// Keeps looking up the words of the job in chunks, giving the UI a chance to
+/// show the progress in between.
+fn process(window: Rc<RefCell<BatchWindow>>) {
+    let finished = window.borrow_mut().process_chunk();
+    if !finished {
+        let ui = window.borrow().ui.clone();
+        ui.queue_main(move || process(window.clone()));
+    }
+}
+
+fn save_button(
+    ui: &UI,
+    text: &str,
+    window: &Rc<RefCell<BatchWindow>>,
+    to_string: fn(&[Vec<String>]) -> String,
+) -> Button {
+    let mut button = Button::new(ui, text);
+    button.on_clicked(ui, {
+        let window = window.clone();
+        move |_| window.borrow().save(to_string)
+    });
+    button
+}
+
+/// Opens a window for transcribing whole files, such as texts, CSV files or
+/// subtitles.
+pub fn open(ui: &UI, state: Rc<RefCell<State>>) {
+    let mut win = Window::new(ui, "Batch Conversion", 500, 400, WindowType::NoMenubar);
+    win.on_closing(ui, {
+        let ui = ui.clone();
+        move |win| win.hide(&ui)
+    });
+
+    let mut vbox = VerticalBox::new(ui);
+    vbox.set_padded(ui, true);
+
+    let progress = ProgressBar::new();
+    let status = Label::new(
+        ui,
+        "Open a text, CSV or subtitle (.srt) file to transcribe.",
+    );
+    let preview = MultilineEntry::new(ui);
+    let header = Checkbox::new(ui, "The first row of a CSV file is a header");
+
+    let window = Rc::new(RefCell::new(BatchWindow {
+        ui: ui.clone(),
+        win: win.clone(),
+        progress: progress.clone(),
+        status: status.clone(),
+        preview: preview.clone(),
+        header: header.clone(),
+        job: None,
+        rows: Vec::new(),
+    }));
+
+    let mut open = Button::new(ui, "Open…");
+    open.on_clicked(ui, {
+        let window = window.clone();
+        move |_| {
+            let opened = window.borrow_mut().open_file(&state.borrow());
+            match opened {
+                Ok(true) => process(window.clone()),
+                Ok(false) => {}
+                Err(e) => {
+                    let window = window.borrow();
+                    window
+                        .win
+                        .modal_err(&window.ui, "Failed opening", &format!("{:#}", e));
+                }
+            }
+        }
+    });
+
+    let mut buttons = HorizontalBox::new(ui);
+    buttons.set_padded(ui, true);
+    buttons.append(ui, open, Compact);
+    buttons.append(
+        ui,
+        save_button(ui, "Save as CSV…", &window, document::to_csv),
+        Compact,
+    );
+    buttons.append(
+        ui,
+        save_button(ui, "Save as Text…", &window, document::to_text),
+        Compact,
+    );
+
+    vbox.append(ui, buttons, Compact);
+    vbox.append(ui, header, Compact);
+    vbox.append(ui, progress, Compact);
+    vbox.append(ui, status, Compact);
+    vbox.append(ui, Label::new(ui, "Preview:"), Compact);
+    vbox.append(ui, preview, Stretchy);
+
+    win.set_child(ui, vbox);
+    win.show(ui);
+}
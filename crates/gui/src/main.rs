@@ -1,48 +1,322 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
+use anyhow::{Context, Result};
+use arboard::Clipboard;
 use iui::{
-    controls::{Entry, Label, VerticalBox},
+    controls::{Button, Entry, Group, HorizontalBox, Label, RadioButtons, VerticalBox},
     prelude::*,
 };
-use phonetics_to_hangul::{arpabet, hangul_builder, ipa_to_hangul};
+use phonetics_to_hangul::{
+    arpabet, fidelity, hangul_builder, ipa_to_hangul,
+    pronunciation_source::PronunciationSource,
+    romanization,
+    user_dictionary::{Field, PendingCorrection, UserDictionary},
+};
 use LayoutStrategy::Compact;
 
+mod batch;
+
+/// One way of transcribing a word.
+struct Variant {
+    ipa: String,
+    hangul: String,
+    source: &'static str,
+}
+
+struct Word {
+    text: String,
+    variants: Vec<Variant>,
+}
+
 struct State {
-    dictionary: arpabet::Dictionary<'static>,
+    dictionary: Rc<arpabet::Dictionary<'static>>,
     builder: hangul_builder::Builder,
     ui: UI,
+    win: Window,
     hangul: Entry,
     pronunciation: Entry,
+    romanization: Entry,
+    fidelity: Label,
+    results: Group,
+    clipboard: Option<Clipboard>,
     word: String,
+    words: Vec<Word>,
+    /// The variant the user chose for each word, by its lowercase text.
+    choices: HashMap<String, usize>,
+    pronunciations: String,
+    hanguls: String,
+    overrides: UserDictionary,
+    /// The edit of the pronunciation or the 한글 that hasn't been remembered
+    /// yet.
+    correction: Option<PendingCorrection>,
+    /// The config file the corrections are saved in. There's none if it
+    /// couldn't be loaded, so that it doesn't get overwritten.
+    overrides_path: Option<PathBuf>,
+}
+
+fn overrides_path() -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join("phonetics-to-hangul")
+            .join("overrides.txt"),
+    )
+}
+
+fn load_overrides(path: &Path) -> Result<UserDictionary> {
+    if !path.exists() {
+        return Ok(UserDictionary::new());
+    }
+    let txt = fs::read_to_string(path).context("Failed reading the corrections.")?;
+    UserDictionary::parse(&txt).context("Failed parsing the corrections.")
 }
 
 impl State {
     fn recalc(&mut self) {
+        let text = self.word.clone();
+        self.words = text
+            .split_whitespace()
+            .map(|text| Word {
+                text: text.to_owned(),
+                variants: self.variants(text),
+            })
+            .collect();
+        self.update_output();
+    }
+
+    /// Lists the user's correction of the word first, followed by all the
+    /// pronunciations CMUdict knows.
+    fn variants(&mut self, word: &str) -> Vec<Variant> {
+        let mut variants = Vec::new();
+        let pronunciations = self.dictionary.look_up_all(word, "en").unwrap_or_default();
+
+        let corrected_ipa = self.overrides.look_up(word).map(|p| p.ipa);
+        let corrected_hangul = self.overrides.hangul(word).map(ToOwned::to_owned);
+        if corrected_ipa.is_some() || corrected_hangul.is_some() {
+            let ipa = corrected_ipa
+                .or_else(|| pronunciations.first().map(|p| p.ipa.clone()))
+                .unwrap_or_else(|| "?".to_owned());
+            let hangul = corrected_hangul.unwrap_or_else(|| self.convert(&ipa));
+            variants.push(Variant {
+                ipa,
+                hangul,
+                source: "correction",
+            });
+        }
+
+        for pronunciation in pronunciations {
+            let hangul = self.convert(&pronunciation.ipa);
+            variants.push(Variant {
+                ipa: pronunciation.ipa,
+                hangul,
+                source: "CMUdict",
+            });
+        }
+
+        variants
+    }
+
+    fn convert(&mut self, ipa: &str) -> String {
+        ipa_to_hangul::convert(&mut self.builder, ipa.chars()).collect()
+    }
+
+    /// The index of the variant chosen for the word.
+    fn choice(&self, word: &Word) -> usize {
+        match self.choices.get(&word.text.to_lowercase()) {
+            Some(&choice) if choice < word.variants.len() => choice,
+            _ => 0,
+        }
+    }
+
+    fn choose(&mut self, index: usize, variant: usize) {
+        if let Some(word) = self.words.get(index) {
+            self.choices.insert(word.text.to_lowercase(), variant);
+        }
+        self.update_output();
+    }
+
+    /// Combines the chosen variants of the words.
+    fn update_output(&mut self) {
         let mut pronunciations = String::new();
         let mut hanguls = String::new();
 
-        for (i, word) in self.word.split_whitespace().enumerate() {
+        for (i, word) in self.words.iter().enumerate() {
             if i != 0 {
                 pronunciations.push(' ');
                 hanguls.push(' ');
             }
 
-            if let Some(pronunciation) = self.dictionary.look_up(word) {
-                pronunciations.extend(pronunciation.clone());
-                hanguls.extend(ipa_to_hangul::convert(&mut self.builder, pronunciation));
-            } else {
-                pronunciations.push('?');
-                hanguls.push('?');
+            match word.variants.get(self.choice(word)) {
+                Some(variant) => {
+                    pronunciations.push_str(&variant.ipa);
+                    hanguls.push_str(&variant.hangul);
+                }
+                None => {
+                    pronunciations.push('?');
+                    hanguls.push('?');
+                }
             }
         }
 
-        self.pronunciation.set_value(&self.ui, &pronunciations);
-        self.hangul.set_value(&self.ui, &hanguls);
+        self.pronunciations = pronunciations;
+        self.hanguls = hanguls;
+        self.correction = None;
+        self.pronunciation.set_value(&self.ui, &self.pronunciations);
+        self.hangul.set_value(&self.ui, &self.hanguls);
+        self.romanization.set_value(
+            &self.ui,
+            &romanization::romanize(&self.hanguls, Default::default()),
+        );
+        self.fidelity.set_text(
+            &self.ui,
+            &format!(
+                "Fidelity: {:.0}%",
+                100.0 * fidelity::score(&self.pronunciations, &self.hanguls)
+            ),
+        );
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) {
+        let result = match &mut self.clipboard {
+            Some(clipboard) => clipboard
+                .set_text(text.to_owned())
+                .map_err(|e| e.to_string()),
+            None => Err("There is no clipboard.".to_owned()),
+        };
+        if let Err(e) = result {
+            self.win.modal_err(&self.ui, "Failed copying", &e);
+        }
+    }
+
+    fn save_overrides(&self) {
+        let path = match &self.overrides_path {
+            Some(path) => path,
+            None => return,
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, self.overrides.to_string()));
+        if let Err(e) = result {
+            self.win
+                .modal_err(&self.ui, "Failed saving the corrections", &e.to_string());
+        }
+    }
+
+    /// Switches the words that are currently entered back to their
+    /// preferred variant, which is the correction if there is one.
+    fn reset_choices(&mut self) {
+        for word in self.word.split_whitespace() {
+            self.choices.remove(&word.to_lowercase());
+        }
+    }
+
+    /// Keeps track of the user's edit of the pronunciation or the 한글,
+    /// without remembering it yet.
+    fn edit_correction(&mut self, field: Field, corrected: &str) {
+        let original = match field {
+            Field::Pronunciation => &self.pronunciations,
+            Field::Hangul => &self.hanguls,
+        };
+        PendingCorrection::record(&mut self.correction, field, &self.word, original, corrected);
+    }
+
+    /// Remembers the edit the user made to the pronunciation or the 한글.
+    /// If it doesn't fit the words, the output is reset instead.
+    fn commit_correction(&mut self) {
+        let correction = match self.correction.take() {
+            Some(correction) => correction,
+            None => return,
+        };
+        if self.overrides.commit(&correction) {
+            self.save_overrides();
+            self.reset_choices();
+            self.recalc();
+        } else {
+            self.update_output();
+        }
+    }
+
+    /// Forgets the corrections of the words that are currently entered.
+    fn forget_corrections(&mut self) {
+        for word in self.word.split_whitespace() {
+            self.overrides.remove(word);
+        }
+        self.save_overrides();
+        self.reset_choices();
+        self.recalc();
+    }
+
+    fn import_overrides(&mut self) {
+        let path = match self.win.open_file(&self.ui) {
+            Some(path) => path,
+            None => return,
+        };
+        let result = fs::read_to_string(&path)
+            .context("Failed reading the user dictionary.")
+            .and_then(|txt| self.overrides.import(&txt));
+        match result {
+            Ok(()) => {
+                self.save_overrides();
+                self.recalc();
+            }
+            Err(e) => self
+                .win
+                .modal_err(&self.ui, "Failed importing", &format!("{:#}", e)),
+        }
+    }
+
+    fn export_overrides(&self) {
+        if let Some(path) = self.win.save_file(&self.ui) {
+            if let Err(e) = fs::write(path, self.overrides.to_string()) {
+                self.win
+                    .modal_err(&self.ui, "Failed exporting", &e.to_string());
+            }
+        }
     }
 }
 
+/// Lists the variants of every word in a group of their own, so the user can
+/// choose which one goes into the combined output.
+fn show_results(state: &Rc<RefCell<State>>) {
+    let s = &mut *state.borrow_mut();
+    let ui = s.ui.clone();
+
+    let mut vbox = VerticalBox::new(&ui);
+    vbox.set_padded(&ui, true);
+
+    for (index, word) in s.words.iter().enumerate() {
+        let mut group = Group::new(&ui, &word.text);
+        if word.variants.is_empty() {
+            group.set_child(&ui, Label::new(&ui, "Not found"));
+        } else {
+            let mut variants = RadioButtons::new(&ui);
+            for variant in &word.variants {
+                variants.append(
+                    &ui,
+                    &format!("{}  {}  ({})", variant.ipa, variant.hangul, variant.source),
+                );
+            }
+            variants.set_selected(&ui, s.choice(word) as i32);
+            variants.on_selected(&ui, {
+                let state = state.clone();
+                move |variant| state.borrow_mut().choose(index, variant as usize)
+            });
+            group.set_child(&ui, variants);
+        }
+        vbox.append(&ui, group, Compact);
+    }
+
+    s.results.set_child(&ui, vbox);
+}
+
 fn main() {
     let dictionary = arpabet::Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
 
@@ -52,25 +326,143 @@ fn main() {
     let mut vbox = VerticalBox::new(&ui);
     vbox.set_padded(&ui, true);
 
-    let hangul = Entry::new(&ui);
-    let pronunciation = Entry::new(&ui);
+    let mut hangul = Entry::new(&ui);
+    let mut pronunciation = Entry::new(&ui);
+    let romanization = Entry::new(&ui);
+    let fidelity = Label::new(&ui, "");
+    let results = Group::new(&ui, "Words");
+
+    let mut overrides = UserDictionary::new();
+    let mut path = overrides_path();
+    if let Some(p) = &path {
+        match load_overrides(p) {
+            Ok(loaded) => overrides = loaded,
+            Err(e) => {
+                win.modal_err(&ui, "Failed loading the corrections", &format!("{:#}", e));
+                path = None;
+            }
+        }
+    }
 
     let state = Rc::new(RefCell::new(State {
-        dictionary,
+        dictionary: Rc::new(dictionary),
         builder: hangul_builder::Builder::new(),
         ui: ui.clone(),
+        win: win.clone(),
         hangul: hangul.clone(),
         pronunciation: pronunciation.clone(),
+        romanization: romanization.clone(),
+        fidelity: fidelity.clone(),
+        results: results.clone(),
+        clipboard: Clipboard::new().ok(),
         word: String::new(),
+        words: Vec::new(),
+        choices: HashMap::new(),
+        pronunciations: String::new(),
+        hanguls: String::new(),
+        overrides,
+        correction: None,
+        overrides_path: path,
     }));
 
     let mut word = Entry::new(&ui);
-    word.on_changed(&ui, move |word| {
-        let mut state = state.borrow_mut();
-        state.word = word;
-        state.recalc();
+    word.on_changed(&ui, {
+        let state = state.clone();
+        move |word| {
+            {
+                let mut state = state.borrow_mut();
+                state.word = word;
+                state.recalc();
+            }
+            show_results(&state);
+        }
     });
 
+    // Editing the pronunciation or the 한글 corrects the transcription of the
+    // words, which is remembered once the user is done with it.
+    pronunciation.on_changed(&ui, {
+        let state = state.clone();
+        move |corrected| {
+            state
+                .borrow_mut()
+                .edit_correction(Field::Pronunciation, &corrected)
+        }
+    });
+    hangul.on_changed(&ui, {
+        let state = state.clone();
+        move |corrected| {
+            state
+                .borrow_mut()
+                .edit_correction(Field::Hangul, &corrected)
+        }
+    });
+
+    let mut remember = Button::new(&ui, "Remember Correction");
+    remember.on_clicked(&ui, {
+        let state = state.clone();
+        move |_| {
+            state.borrow_mut().commit_correction();
+            show_results(&state);
+        }
+    });
+
+    let mut forget = Button::new(&ui, "Forget Corrections");
+    forget.on_clicked(&ui, {
+        let state = state.clone();
+        move |_| {
+            state.borrow_mut().forget_corrections();
+            show_results(&state);
+        }
+    });
+    let mut import = Button::new(&ui, "Import…");
+    import.on_clicked(&ui, {
+        let state = state.clone();
+        move |_| {
+            state.borrow_mut().import_overrides();
+            show_results(&state);
+        }
+    });
+    let mut export = Button::new(&ui, "Export…");
+    export.on_clicked(&ui, {
+        let state = state.clone();
+        move |_| state.borrow().export_overrides()
+    });
+    let mut convert_file = Button::new(&ui, "Convert File…");
+    convert_file.on_clicked(&ui, {
+        let ui = ui.clone();
+        let state = state.clone();
+        move |_| batch::open(&ui, state.clone())
+    });
+
+    let mut copy_hangul = Button::new(&ui, "Copy 한글");
+    copy_hangul.on_clicked(&ui, {
+        let state = state.clone();
+        move |_| {
+            let state = &mut *state.borrow_mut();
+            let hanguls = state.hanguls.clone();
+            state.copy_to_clipboard(&hanguls);
+        }
+    });
+    let mut copy_romanization = Button::new(&ui, "Copy Romanization");
+    copy_romanization.on_clicked(&ui, move |_| {
+        let state = &mut *state.borrow_mut();
+        let romanization = romanization::romanize(&state.hanguls, Default::default());
+        state.copy_to_clipboard(&romanization);
+    });
+
+    let mut copy_buttons = HorizontalBox::new(&ui);
+    copy_buttons.set_padded(&ui, true);
+    copy_buttons.append(&ui, copy_hangul, Compact);
+    copy_buttons.append(&ui, copy_romanization, Compact);
+
+    let mut buttons = HorizontalBox::new(&ui);
+    buttons.set_padded(&ui, true);
+    buttons.append(&ui, remember, Compact);
+    buttons.append(&ui, forget, Compact);
+    buttons.append(&ui, import, Compact);
+    buttons.append(&ui, export, Compact);
+    buttons.append(&ui, convert_file, Compact);
+
     vbox.append(&ui, Label::new(&ui, "Word:"), Compact);
     vbox.append(&ui, word, Compact);
 
@@ -80,6 +472,14 @@ fn main() {
     vbox.append(&ui, Label::new(&ui, "한글:"), Compact);
     vbox.append(&ui, hangul, Compact);
 
+    vbox.append(&ui, Label::new(&ui, "Romanization:"), Compact);
+    vbox.append(&ui, romanization, Compact);
+
+    vbox.append(&ui, fidelity, Compact);
+    vbox.append(&ui, copy_buttons, Compact);
+    vbox.append(&ui, results, Compact);
+    vbox.append(&ui, buttons, Compact);
+
     win.set_child(&ui, vbox);
     win.show(&ui);
     ui.main();
@@ -0,0 +1,263 @@
+use std::{collections::HashMap, fmt, iter};
+
+use phonetics_to_hangul::{
+    accent::Accent,
+    arpabet, batch, ipa_to_hangul,
+    pronunciation_source::{Pronunciation, PronunciationSource},
+    romanization::{self, System},
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+/** How the pronunciations are transcribed. */
+export interface Options {
+    /** The accent of English. Defaults to `"american"`. */
+    accent?: "american" | "british";
+    /** The system the 한글 is romanized with. Defaults to `"rr"`. */
+    romanization?: "rr" | "mr" | "yale";
+}
+
+/** A pronunciation of a word, along with its 한글. */
+export interface Transcription {
+    /** The dictionary the pronunciation comes from. */
+    source: string;
+    ipa: string;
+    hangul: string;
+    romanization: string;
+}
+
+/** A text that was transcribed word by word. */
+export interface TextTranscription {
+    ipa: string;
+    hangul: string;
+    romanization: string;
+    /** The words without a known pronunciation, which are kept as is. */
+    unknownWords: string[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Options")]
+    pub type JsOptions;
+
+    #[wasm_bindgen(typescript_type = "Transcription[]")]
+    pub type Transcriptions;
+
+    #[wasm_bindgen(typescript_type = "TextTranscription")]
+    pub type JsTextTranscription;
+}
+
+thread_local! {
+    static DICTIONARY: arpabet::Dictionary<'static> =
+        arpabet::Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+}
+
+#[derive(Default, Deserialize)]
+struct Options {
+    accent: Option<String>,
+    romanization: Option<String>,
+}
+
+struct Settings {
+    accent: Accent,
+    system: System,
+}
+
+impl Settings {
+    fn new(options: Options) -> anyhow::Result<Self> {
+        Ok(Self {
+            accent: parse_or_default(options.accent.as_deref())?,
+            system: parse_or_default(options.romanization.as_deref())?,
+        })
+    }
+
+    fn parse(options: Option<JsOptions>) -> Result<Self, JsValue> {
+        let options = match options {
+            Some(options) => serde_wasm_bindgen::from_value(options.into()).map_err(error)?,
+            None => Options::default(),
+        };
+        Self::new(options).map_err(error)
+    }
+
+    fn ipa_options(&self) -> ipa_to_hangul::Options {
+        ipa_to_hangul::Options {
+            accent: self.accent,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Transcription {
+    source: String,
+    ipa: String,
+    hangul: String,
+    romanization: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TextTranscription {
+    ipa: String,
+    hangul: String,
+    romanization: String,
+    unknown_words: Vec<String>,
+}
+
+fn error(e: impl fmt::Display) -> JsValue {
+    js_sys::Error::new(&format!("{:#}", e)).into()
+}
+
+fn parse_or_default<T>(value: Option<&str>) -> Result<T, T::Err>
+where
+    T: std::str::FromStr + Default,
+{
+    Ok(value.map(str::parse).transpose()?.unwrap_or_default())
+}
+
+fn to_js<T: JsCast>(value: &impl Serialize) -> Result<T, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(value)
+        .map_err(error)?
+        .unchecked_into())
+}
+
+fn look_up_words(words: &[String], accent: Accent) -> Vec<batch::Outcome> {
+    DICTIONARY.with(|dictionary| {
+        words
+            .iter()
+            .map(|word| {
+                let pronunciations = dictionary
+                    .look_up_all_accent(word, accent)
+                    .into_iter()
+                    .map(|ipa| Pronunciation {
+                        word: word.clone(),
+                        ipa,
+                        part_of_speech: None,
+                    })
+                    .collect::<Vec<_>>();
+                if pronunciations.is_empty() {
+                    batch::Outcome::NotFound
+                } else {
+                    batch::Outcome::Found {
+                        source: dictionary.name().to_owned(),
+                        pronunciations,
+                    }
+                }
+            })
+            .collect()
+    })
+}
+
+fn look_up_transcriptions(word: &str, settings: &Settings) -> Vec<Transcription> {
+    look_up_words(&[word.to_owned()], settings.accent)
+        .iter()
+        .flat_map(|outcome| outcome.transcriptions(true, settings.ipa_options()))
+        .map(|transcription| Transcription {
+            romanization: romanization::romanize(&transcription.hangul, settings.system),
+            source: transcription.source,
+            ipa: transcription.ipa,
+            hangul: transcription.hangul,
+        })
+        .collect()
+}
+
+fn transcribe(text: &str, settings: &Settings) -> TextTranscription {
+    let words = batch::words(iter::once(text));
+
+    let mut transcriptions = HashMap::new();
+    let mut unknown_words = Vec::new();
+    for (word, outcome) in words.iter().zip(look_up_words(&words, settings.accent)) {
+        match outcome.transcriptions(false, settings.ipa_options()).pop() {
+            Some(transcription) => {
+                transcriptions.insert(word.to_lowercase(), transcription);
+            }
+            None => unknown_words.push(word.clone()),
+        }
+    }
+
+    let (ipa, hangul) =
+        batch::transcribe_text(text, |word| transcriptions.get(&word.to_lowercase()));
+    TextTranscription {
+        romanization: romanization::romanize(&hangul, settings.system),
+        ipa,
+        hangul,
+        unknown_words,
+    }
+}
+
+/// Looks up all the pronunciations of the word in CMUdict and transcribes
+/// them into 한글. The list is empty if the word is unknown.
+#[wasm_bindgen(js_name = lookup)]
+pub fn look_up(word: &str, options: Option<JsOptions>) -> Result<Transcriptions, JsValue> {
+    let settings = Settings::parse(options)?;
+    to_js(&look_up_transcriptions(word, &settings))
+}
+
+/// Transcribes a pronunciation written in IPA into 한글.
+#[wasm_bindgen(js_name = ipaToHangul)]
+pub fn convert_ipa(ipa: &str, options: Option<JsOptions>) -> Result<String, JsValue> {
+    let settings = Settings::parse(options)?;
+    Ok(
+        ipa_to_hangul::convert_with(&mut Default::default(), ipa.chars(), settings.ipa_options())
+            .collect(),
+    )
+}
+
+/// Transcribes the text word by word, using the first pronunciation CMUdict
+/// knows for each word. Punctuation and unknown words are kept as is.
+#[wasm_bindgen(js_name = transcribeText)]
+pub fn transcribe_text(
+    text: &str,
+    options: Option<JsOptions>,
+) -> Result<JsTextTranscription, JsValue> {
+    let settings = Settings::parse(options)?;
+    to_js(&transcribe(text, &settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(accent: Option<&str>, romanization: Option<&str>) -> anyhow::Result<Settings> {
+        Settings::new(Options {
+            accent: accent.map(ToOwned::to_owned),
+            romanization: romanization.map(ToOwned::to_owned),
+        })
+    }
+
+    #[test]
+    fn defaults_omitted_options() {
+        let defaults = settings(None, None).unwrap();
+        assert_eq!(defaults.accent, Accent::American);
+        assert_eq!(defaults.system, System::RevisedRomanization);
+
+        let british = settings(Some("british"), Some("yale")).unwrap();
+        assert_eq!(british.accent, Accent::British);
+        assert_eq!(british.system, System::Yale);
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert!(settings(Some("australian"), None).is_err());
+        assert!(settings(None, Some("hepburn")).is_err());
+    }
+
+    #[test]
+    fn looks_up_each_accent_separately() {
+        let american = look_up_transcriptions("car", &settings(None, None).unwrap());
+        let british = look_up_transcriptions("car", &settings(Some("uk"), None).unwrap());
+        assert_eq!(american[0].ipa, "kɑɹ");
+        assert_eq!(british[0].ipa, "kɑː");
+        assert!(look_up_transcriptions("xyzzyq", &settings(None, None).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_words() {
+        let transcription = transcribe("Hello xyzzyq, world!", &settings(None, None).unwrap());
+        assert_eq!(transcription.unknown_words, ["xyzzyq"]);
+        assert!(transcription.hangul.contains("xyzzyq"));
+        assert!(transcription.hangul.ends_with('!'));
+    }
+}
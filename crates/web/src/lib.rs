@@ -1,8 +1,55 @@
-#![recursion_limit = "256"]
+#![recursion_limit = "512"]
 
-use phonetics_to_hangul::{arpabet, hangul_builder, ipa_to_hangul};
+use phonetics_to_hangul::{
+    accent::Accent,
+    arpabet, fidelity, hangul_builder, ipa_to_hangul,
+    romanization::{self, System},
+    user_dictionary::{Field, PendingCorrection, UserDictionary},
+};
 use wasm_bindgen::prelude::*;
-use yew::prelude::*;
+use yew::{
+    format::Text,
+    prelude::*,
+    services::{
+        reader::{FileData, ReaderService, ReaderTask},
+        storage::{Area, StorageService},
+    },
+};
+
+/// The key the user's corrections are stored under in the local storage, in
+/// the format of a user dictionary.
+const OVERRIDES_KEY: &str = "phonetics-to-hangul.overrides";
+
+/// The key the recent conversions are stored under in the session storage,
+/// one per line.
+const HISTORY_KEY: &str = "phonetics-to-hangul.history";
+
+/// How many conversions the history keeps.
+const HISTORY_LEN: usize = 20;
+
+/// Which of the inputs the transcription starts from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    English,
+    Ipa,
+}
+
+/// A transcription as it is encoded in the hash of the URL, so that it can be
+/// shared, e.g. `#en=hello&accent=uk&romanization=mr`. The options are left
+/// out while they are the defaults.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Query {
+    mode: Mode,
+    text: String,
+    accent: Accent,
+    system: System,
+}
+
+/// A conversion in the history, along with the 한글 it resulted in.
+struct Conversion {
+    query: Query,
+    hangul: String,
+}
 
 struct State {
     link: ComponentLink<Self>,
@@ -10,18 +57,129 @@ struct State {
     builder: hangul_builder::Builder,
     pronunciations: String,
     hanguls: String,
+    steps: Vec<ipa_to_hangul::Step>,
+    fidelity: f32,
+    mode: Mode,
     word: String,
+    accent: Accent,
+    system: System,
+    overrides: UserDictionary,
+    /// The correction of the pronunciation that is being typed.
+    correction: Option<PendingCorrection>,
+    overrides_error: Option<String>,
+    storage: Option<StorageService>,
+    reader: ReaderService,
+    import: Option<ReaderTask>,
+    history: Vec<Conversion>,
+    session: Option<StorageService>,
 }
 
 enum Message {
     WordChanged(InputData),
     PronunciationChanged(InputData),
+    HangulChanged(ChangeData),
+    OverridesChanged(ChangeData),
+    ImportOverrides(ChangeData),
+    OverridesImported(FileData),
+    AccentChanged(ChangeData),
+    SystemChanged(ChangeData),
+    Commit,
+    Restore(usize),
+}
+
+/// Percent-encodes the text, so it can be part of a URL.
+fn percent_encode(text: &str) -> String {
+    js_sys::encode_uri_component(text).into()
+}
+
+/// Decodes the escapes of [`percent_encode`]. Text that isn't validly
+/// encoded, like a lone `%` typed into the URL by hand, is kept as is.
+fn percent_decode(text: &str) -> String {
+    js_sys::decode_uri_component(text).map_or_else(|_| text.to_owned(), Into::into)
+}
+
+fn accent_name(accent: Accent) -> &'static str {
+    match accent {
+        Accent::American => "us",
+        Accent::British => "uk",
+    }
+}
+
+fn system_name(system: System) -> &'static str {
+    match system {
+        System::RevisedRomanization => "rr",
+        System::McCuneReischauer => "mr",
+        System::Yale => "yale",
+    }
+}
+
+impl Query {
+    fn to_hash(&self) -> String {
+        let mode = match self.mode {
+            Mode::English => "en",
+            Mode::Ipa => "ipa",
+        };
+        let mut hash = format!("#{}={}", mode, percent_encode(&self.text));
+        if self.accent != Accent::default() {
+            hash.push_str("&accent=");
+            hash.push_str(accent_name(self.accent));
+        }
+        if self.system != System::default() {
+            hash.push_str("&romanization=");
+            hash.push_str(system_name(self.system));
+        }
+        hash
+    }
+
+    /// Parses the hash of a URL. Unknown parameters and options are ignored.
+    fn from_hash(hash: &str) -> Option<Self> {
+        let mut query = None;
+        let mut accent = Accent::default();
+        let mut system = System::default();
+
+        for parameter in hash.trim_start_matches('#').split('&') {
+            let (key, value) = match parameter.split_once('=') {
+                Some((key, value)) => (key, percent_decode(value)),
+                None => continue,
+            };
+            match key {
+                "en" => query = Some((Mode::English, value)),
+                "ipa" => query = Some((Mode::Ipa, value)),
+                "accent" => accent = value.parse().unwrap_or_default(),
+                "romanization" => system = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+
+        let (mode, text) = query?;
+        Some(Self {
+            mode,
+            text,
+            accent,
+            system,
+        })
+    }
 }
 
 impl State {
+    fn options(&self) -> ipa_to_hangul::Options {
+        ipa_to_hangul::Options {
+            accent: self.accent,
+        }
+    }
+
+    fn recalc(&mut self) {
+        match self.mode {
+            Mode::English => self.recalc_from_word(),
+            Mode::Ipa => self.recalc_from_pronunciation(),
+        }
+    }
+
     fn recalc_from_word(&mut self) {
+        let options = self.options();
         self.pronunciations.clear();
         self.hanguls.clear();
+        self.steps.clear();
 
         let word = if self.word.is_empty() {
             "Example Text"
@@ -35,20 +193,40 @@ impl State {
                 self.hanguls.push(' ');
             }
 
-            if let Some(pronunciation) = self.dictionary.look_up(word) {
-                self.pronunciations.extend(pronunciation.clone());
-                self.hanguls
-                    .extend(ipa_to_hangul::convert(&mut self.builder, pronunciation));
+            // The user's corrections take precedence over the dictionary and
+            // the conversion.
+            let pronunciation = match self.overrides.look_up(word) {
+                Some(pronunciation) => Some(pronunciation.ipa),
+                None => self.dictionary.look_up_accent(word, self.accent),
+            };
+
+            match &pronunciation {
+                Some(pronunciation) => self.pronunciations.push_str(pronunciation),
+                None => self.pronunciations.push('?'),
+            }
+
+            if let Some(hangul) = self.overrides.hangul(word) {
+                self.hanguls.push_str(hangul);
+            } else if let Some(pronunciation) = pronunciation {
+                self.steps.extend(ipa_to_hangul::trace(
+                    &mut self.builder,
+                    pronunciation.chars(),
+                    options,
+                ));
+                self.hanguls.extend(self.builder.finish());
             } else {
-                self.pronunciations.push('?');
                 self.hanguls.push('?');
             }
         }
+
+        self.fidelity = fidelity::score(&self.pronunciations, &self.hanguls);
     }
 
     fn recalc_from_pronunciation(&mut self) {
+        let options = self.options();
         self.word.clear();
         self.hanguls.clear();
+        self.steps.clear();
 
         let pronunciations = if self.pronunciations.is_empty() {
             "ɪɡzæmpʌl tɛkst"
@@ -61,10 +239,205 @@ impl State {
                 self.hanguls.push(' ');
             }
 
-            self.hanguls.extend(ipa_to_hangul::convert(
+            self.steps.extend(ipa_to_hangul::trace(
                 &mut self.builder,
                 pronunciation.chars(),
+                options,
             ));
+            self.hanguls.extend(self.builder.finish());
+        }
+
+        self.fidelity = fidelity::score(pronunciations, &self.hanguls);
+    }
+
+    /// The current transcription, unless the example is shown.
+    fn query(&self) -> Option<Query> {
+        let text = match self.mode {
+            Mode::English => &self.word,
+            Mode::Ipa => &self.pronunciations,
+        };
+        if text.is_empty() {
+            return None;
+        }
+        Some(Query {
+            mode: self.mode,
+            text: text.clone(),
+            accent: self.accent,
+            system: self.system,
+        })
+    }
+
+    fn restore(&mut self, query: Query) {
+        self.mode = query.mode;
+        self.accent = query.accent;
+        self.system = query.system;
+        match query.mode {
+            Mode::English => self.word = query.text,
+            Mode::Ipa => self.pronunciations = query.text,
+        }
+        self.recalc();
+    }
+
+    /// Replaces the URL with one that leads to the current transcription,
+    /// without adding an entry to the browser's history.
+    fn update_url(&self) {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        let url = match self.query() {
+            Some(query) => query.to_hash(),
+            None => window.location().pathname().unwrap_or_default(),
+        };
+        if let Ok(history) = window.history() {
+            let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+        }
+    }
+
+    /// Adds the current transcription to the top of the history.
+    fn add_to_history(&mut self) {
+        let query = match self.query() {
+            Some(query) => query,
+            None => return,
+        };
+        self.history.retain(|conversion| conversion.query != query);
+        self.history.insert(
+            0,
+            Conversion {
+                query,
+                hangul: self.hanguls.clone(),
+            },
+        );
+        self.history.truncate(HISTORY_LEN);
+
+        let history: Text = Ok(self
+            .history
+            .iter()
+            .map(|conversion| format!("{}\t{}\n", conversion.query.to_hash(), conversion.hangul))
+            .collect());
+        if let Some(session) = &mut self.session {
+            session.store(HISTORY_KEY, history);
+        }
+    }
+
+    fn save_overrides(&mut self) {
+        let overrides: Text = Ok(self.overrides.to_string());
+        if let Some(storage) = &mut self.storage {
+            storage.store(OVERRIDES_KEY, overrides);
+        }
+    }
+
+    fn view_overrides(&self) -> Html {
+        let export = format!(
+            "data:text/plain;charset=utf-8,{}",
+            percent_encode(&self.overrides.to_string())
+        );
+
+        html! {
+            <details>
+                <summary>{"Corrections"}</summary>
+                <p>
+                    {"Correct the pronunciation or the 한글 of a word above and press Enter, and it is \
+                    remembered here, as a user dictionary."}
+                </p>
+                <textarea
+                    rows=8
+                    placeholder="word\tpronunciation\t한글"
+                    value={self.overrides.to_string()}
+                    onchange=self.link.callback(Message::OverridesChanged)
+                />
+                { for self.overrides_error.iter().map(|e| html! { <p class="error">{e}</p> }) }
+                <p>
+                    {"Import: "}
+                    <input
+                        type="file"
+                        accept=".txt,.tsv"
+                        onchange=self.link.callback(Message::ImportOverrides)
+                    />
+                </p>
+                <p>
+                    <a href=export download="user-dictionary.txt">{"Export"}</a>
+                </p>
+            </details>
+        }
+    }
+
+    fn view_options(&self) -> Html {
+        html! {
+            <p>
+                {"Accent: "}
+                <select onchange=self.link.callback(Message::AccentChanged)>
+                    <option value="us" selected=self.accent == Accent::American>
+                        {"American"}
+                    </option>
+                    <option value="uk" selected=self.accent == Accent::British>
+                        {"British"}
+                    </option>
+                </select>
+                {" Romanization: "}
+                <select onchange=self.link.callback(Message::SystemChanged)>
+                    <option value="rr" selected=self.system == System::RevisedRomanization>
+                        {"Revised"}
+                    </option>
+                    <option value="mr" selected=self.system == System::McCuneReischauer>
+                        {"McCune–Reischauer"}
+                    </option>
+                    <option value="yale" selected=self.system == System::Yale>
+                        {"Yale"}
+                    </option>
+                </select>
+            </p>
+        }
+    }
+
+    fn view_history(&self) -> Html {
+        html! {
+            <details>
+                <summary>{"History"}</summary>
+                <ul>
+                    { for self.history.iter().enumerate().map(|(i, conversion)| html! {
+                        <li>
+                            <a
+                                href=conversion.query.to_hash()
+                                onclick=self.link.callback(move |_| Message::Restore(i))
+                            >
+                                {&conversion.query.text}
+                            </a>
+                            {format!(" → {}", conversion.hangul)}
+                        </li>
+                    }) }
+                </ul>
+            </details>
+        }
+    }
+
+    fn view_explanation(&self) -> Html {
+        html! {
+            <details>
+                <summary>{"Explanation"}</summary>
+                <table>
+                    <tr>
+                        <th>{"IPA"}</th>
+                        <th>{"Jamo"}</th>
+                        <th>{"Rule"}</th>
+                        <th>{"Context"}</th>
+                        <th>{"Position"}</th>
+                    </tr>
+                    { for self.steps.iter().map(Self::view_step) }
+                </table>
+            </details>
+        }
+    }
+
+    fn view_step(step: &ipa_to_hangul::Step) -> Html {
+        html! {
+            <tr>
+                <td>{&step.ipa}</td>
+                <td>{&step.jamo}</td>
+                <td>{step.rule}</td>
+                <td>{step.context()}</td>
+                <td>{format!("{} → {}", step.before, step.after)}</td>
+            </tr>
         }
     }
 }
@@ -76,32 +449,166 @@ impl Component for State {
 
     fn create((): (), link: ComponentLink<Self>) -> Self {
         let dictionary = arpabet::Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+        let storage = StorageService::new(Area::Local).ok();
+        let overrides = storage
+            .as_ref()
+            .and_then(|storage| storage.restore::<Text>(OVERRIDES_KEY).ok())
+            .and_then(|overrides| UserDictionary::parse(&overrides).ok())
+            .unwrap_or_default();
+        let session = StorageService::new(Area::Session).ok();
+        let history = session
+            .as_ref()
+            .and_then(|session| session.restore::<Text>(HISTORY_KEY).ok())
+            .map(|history| {
+                history
+                    .lines()
+                    .filter_map(|line| {
+                        let (hash, hangul) = line.split_once('\t')?;
+                        Some(Conversion {
+                            query: Query::from_hash(hash)?,
+                            hangul: hangul.to_owned(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
         let mut state = Self {
             link,
             dictionary,
             builder: hangul_builder::Builder::new(),
             pronunciations: String::new(),
             hanguls: String::new(),
+            steps: Vec::new(),
+            fidelity: 0.0,
+            mode: Mode::English,
             word: String::new(),
+            accent: Accent::default(),
+            system: System::default(),
+            overrides,
+            correction: None,
+            overrides_error: None,
+            storage,
+            reader: ReaderService::new(),
+            import: None,
+            history,
+            session,
         };
-        state.recalc_from_word();
+        let query = web_sys::window()
+            .and_then(|window| window.location().hash().ok())
+            .and_then(|hash| Query::from_hash(&hash));
+        match query {
+            Some(query) => state.restore(query),
+            None => state.recalc_from_word(),
+        }
         state
     }
 
     fn update(&mut self, message: Message) -> ShouldRender {
         match message {
             Message::WordChanged(change) => {
+                self.mode = Mode::English;
+                self.correction = None;
                 self.word.clear();
                 self.word.push_str(&change.value);
                 self.recalc_from_word();
             }
+            Message::PronunciationChanged(change) if !self.word.is_empty() => {
+                // With a word, editing its pronunciation corrects it once the
+                // edit is committed.
+                PendingCorrection::record(
+                    &mut self.correction,
+                    Field::Pronunciation,
+                    &self.word,
+                    &self.pronunciations,
+                    &change.value,
+                );
+                self.pronunciations = change.value;
+            }
             Message::PronunciationChanged(change) => {
+                self.mode = Mode::Ipa;
                 self.pronunciations.clear();
                 self.pronunciations.push_str(&change.value);
                 self.recalc_from_pronunciation();
             }
+            Message::HangulChanged(ChangeData::Value(hangul)) => {
+                if self.word.is_empty() {
+                    return false;
+                }
+                let mut correction =
+                    PendingCorrection::new(Field::Hangul, &self.word, &self.hanguls);
+                correction.edit(&hangul);
+                if self.overrides.commit(&correction) {
+                    self.save_overrides();
+                }
+                self.recalc_from_word();
+            }
+            Message::OverridesChanged(ChangeData::Value(overrides)) => {
+                match UserDictionary::parse(&overrides) {
+                    Ok(overrides) => {
+                        self.overrides = overrides;
+                        self.overrides_error = None;
+                        self.save_overrides();
+                        self.recalc_from_word();
+                    }
+                    Err(e) => self.overrides_error = Some(e.to_string()),
+                }
+            }
+            Message::ImportOverrides(ChangeData::Files(files)) => {
+                if let Some(file) = files.get(0) {
+                    let callback = self.link.callback(Message::OverridesImported);
+                    self.import = self.reader.read_file(file, callback).ok();
+                }
+                return false;
+            }
+            Message::OverridesImported(file) => {
+                self.import = None;
+                match self
+                    .overrides
+                    .import(&String::from_utf8_lossy(&file.content))
+                {
+                    Ok(()) => {
+                        self.overrides_error = None;
+                        self.save_overrides();
+                        self.recalc_from_word();
+                    }
+                    Err(e) => {
+                        self.overrides_error =
+                            Some(format!("Failed importing {}: {}", file.name, e))
+                    }
+                }
+            }
+            Message::AccentChanged(ChangeData::Select(select)) => {
+                self.accent = select.value().parse().unwrap_or_default();
+                self.recalc();
+                self.add_to_history();
+            }
+            Message::SystemChanged(ChangeData::Select(select)) => {
+                self.system = select.value().parse().unwrap_or_default();
+                self.add_to_history();
+            }
+            Message::Commit => {
+                if let Some(correction) = self.correction.take() {
+                    if self.overrides.commit(&correction) {
+                        self.save_overrides();
+                    }
+                    self.recalc_from_word();
+                }
+                self.add_to_history();
+            }
+            Message::Restore(index) => {
+                if let Some(conversion) = self.history.get(index) {
+                    let query = conversion.query.clone();
+                    self.restore(query);
+                }
+            }
+            Message::HangulChanged(_)
+            | Message::OverridesChanged(_)
+            | Message::ImportOverrides(_)
+            | Message::AccentChanged(_)
+            | Message::SystemChanged(_) => return false,
         }
 
+        self.update_url();
         true
     }
 
@@ -112,6 +619,7 @@ impl Component for State {
     fn view(&self) -> Html {
         html! {
             <div>
+                { self.view_options() }
                 <p>
                     {"English Text:"}
                     <div class="result">
@@ -120,6 +628,7 @@ impl Component for State {
                             placeholder="Example Text"
                             value={&self.word}
                             oninput=self.link.callback(|w| Message::WordChanged(w))
+                            onchange=self.link.callback(|_| Message::Commit)
                         />
                     </div>
                 </p>
@@ -131,6 +640,7 @@ impl Component for State {
                             placeholder="ɪɡzæmpʌl tɛkst"
                             value={&self.pronunciations}
                             oninput=self.link.callback(|w| Message::PronunciationChanged(w))
+                            onchange=self.link.callback(|_| Message::Commit)
                         />
                     </div>
                 </p>
@@ -141,10 +651,34 @@ impl Component for State {
                             type="text"
                             placeholder="익샘펄 댘슽"
                             value={&self.hanguls}
+                            onchange=self.link.callback(Message::HangulChanged)
+                        />
+                    </div>
+                </p>
+                <p>
+                    {"Romanization:"}
+                    <div class="result">
+                        <input
+                            type="text"
+                            placeholder="iksaempeol daeksut"
+                            value={romanization::romanize(&self.hanguls, self.system)}
                             readonly=true
                         />
                     </div>
                 </p>
+                <p>
+                    {"Fidelity:"}
+                    <div class="result">
+                        <input
+                            type="text"
+                            value={format!("{:.0}%", 100.0 * self.fidelity)}
+                            readonly=true
+                        />
+                    </div>
+                </p>
+                { self.view_explanation() }
+                { self.view_history() }
+                { self.view_overrides() }
             </div>
         }
     }
@@ -155,3 +689,52 @@ pub fn main() {
     yew::initialize();
     App::<State>::new().mount_to_body();
 }
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    #[wasm_bindgen_test]
+    fn round_trips_queries() {
+        let texts = [
+            "안녕하세요",
+            "ˈhɛloʊ ˈwɝld",
+            "rock & roll",
+            "a=b",
+            "100% sure",
+            "%",
+        ];
+        for text in &texts {
+            let query = Query {
+                mode: Mode::Ipa,
+                text: text.to_string(),
+                accent: Accent::British,
+                system: System::Yale,
+            };
+            assert_eq!(Query::from_hash(&query.to_hash()), Some(query));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn escapes_the_separators() {
+        assert_eq!(percent_encode("a&b=c%"), "a%26b%3Dc%25");
+        assert_eq!(
+            Query::from_hash("#en=a%26b%3Dc&accent=uk"),
+            Some(Query {
+                mode: Mode::English,
+                text: "a&b=c".to_owned(),
+                accent: Accent::British,
+                system: System::default(),
+            })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn keeps_a_lone_percent_sign() {
+        assert_eq!(percent_decode("%"), "%");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(Query::from_hash("#en=100%").unwrap().text, "100%");
+    }
+}
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use phonetics_to_hangul::{hangul_builder::Builder, ipa_to_hangul};
+
+fuzz_target!(|phonetics: &str| {
+    let mut builder = Builder::new();
+    for _ in ipa_to_hangul::convert(&mut builder, phonetics.chars()) {}
+});
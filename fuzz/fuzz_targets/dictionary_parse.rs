@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use phonetics_to_hangul::{accent::Accent, arpabet::Dictionary};
+
+fuzz_target!(|txt: &str| {
+    if let Some(dictionary) = Dictionary::parse(txt) {
+        for word in txt.lines().filter_map(|l| l.split("  ").next()) {
+            if let Some(ipa) = dictionary.look_up(word) {
+                for _ in ipa {}
+            }
+            dictionary.look_up_accent(word, Accent::British);
+        }
+    }
+});
@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// The accents of English that pronunciations can follow.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Accent {
+    /// General American, which is rhotic. This is what CMUdict describes.
+    #[default]
+    American,
+    /// Received Pronunciation, which is non-rhotic. Korean loanwords mostly
+    /// follow it, e.g. in spelling `-er` as 어.
+    British,
+}
+
+impl Accent {
+    /// The tags Wiktionary marks pronunciations in the accent with.
+    pub fn wiktionary_tags(self) -> &'static [&'static str] {
+        match self {
+            Accent::American => &["US", "General-American"],
+            Accent::British => &["UK", "Received-Pronunciation"],
+        }
+    }
+}
+
+impl FromStr for Accent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "us" | "american" | "ga" => Accent::American,
+            "uk" | "british" | "rp" => Accent::British,
+            _ => bail!("Unknown accent `{}`.", s),
+        })
+    }
+}
@@ -1,9 +1,15 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, iter};
 
+use anyhow::Result;
 use unicase::UniCase;
 
-fn map_char_to_ipa(s: &str) -> &'static str {
-    match s.trim_end_matches(|c: char| c.is_numeric()) {
+use crate::{
+    accent::Accent,
+    pronunciation_source::{Pronunciation, PronunciationSource},
+};
+
+fn map_char_to_ipa(s: &str) -> Option<&'static str> {
+    Some(match s.trim_end_matches(|c: char| c.is_numeric()) {
         "AA" => "ɑ",
         "AE" => "æ",
         "AH" => "ʌ",
@@ -43,36 +49,149 @@ fn map_char_to_ipa(s: &str) -> &'static str {
         "Y" => "j",
         "Z" => "z",
         "ZH" => "ʒ",
-        _ => panic!("Unknown ARPABET character."),
+        _ => return None,
+    })
+}
+
+/// CMUdict marks the stress of every vowel with a digit.
+fn is_vowel(phoneme: &str) -> bool {
+    phoneme.ends_with(|c: char| c.is_numeric())
+}
+
+/// Derives a Received Pronunciation from the General American one. The r is
+/// dropped unless a vowel follows and the vowel before it is lengthened or
+/// turned into a diphthong instead. The LOT vowel becomes ɒ.
+fn to_british_ipa(arpa_chars: &str) -> String {
+    let phonemes = arpa_chars.split_whitespace().collect::<Vec<_>>();
+    let drops_r = |i: usize| {
+        phonemes.get(i) == Some(&"R") && !matches!(phonemes.get(i + 1), Some(p) if is_vowel(p))
+    };
+
+    let mut ipa = String::new();
+    for (i, &phoneme) in phonemes.iter().enumerate() {
+        let before_r = drops_r(i + 1);
+        ipa.push_str(match phoneme.trim_end_matches(|c: char| c.is_numeric()) {
+            "R" if drops_r(i) => "",
+            "ER" if phoneme == "ER0" => "ə",
+            "ER" => "ɜː",
+            "AA" if before_r => "ɑː",
+            "AA" => "ɒ",
+            "AO" => "ɔː",
+            "OW" if before_r => "ɔː",
+            "OW" => "əʊ",
+            "IH" | "IY" if before_r => "ɪə",
+            "EH" | "EY" | "AE" if before_r => "ɛə",
+            "UH" | "UW" if before_r => "ʊə",
+            "AY" if before_r => "aɪə",
+            "AW" if before_r => "aʊə",
+            "AH" if before_r => "ə",
+            _ => map_char_to_ipa(phoneme).unwrap_or_default(),
+        });
     }
+    ipa
 }
 
 pub struct Dictionary<'txt> {
     map: HashMap<UniCase<&'txt str>, &'txt str>,
+    accent: Accent,
 }
 
 impl<'txt> Dictionary<'txt> {
+    /// Parses a dictionary in the format of CMUdict. Returns `None` if a line
+    /// has no pronunciation or uses an unknown ARPABET symbol.
     pub fn parse(txt: &'txt str) -> Option<Self> {
         Some(Self {
             map: txt
                 .lines()
                 .filter(|l| !l.starts_with(";;;"))
                 .map(|l| {
-                    let mut splits = l.splitn(2, "  ");
-                    Some((splits.next()?.into(), splits.next()?))
+                    let (word, arpa_chars) = l.split_once("  ")?;
+                    if !arpa_chars
+                        .split_whitespace()
+                        .all(|c| map_char_to_ipa(c).is_some())
+                    {
+                        return None;
+                    }
+                    Some((word.into(), arpa_chars))
                 })
                 .collect::<Option<_>>()?,
+            accent: Accent::default(),
         })
     }
 
+    /// Sets the accent the dictionary provides pronunciations in as a
+    /// [`PronunciationSource`]. Defaults to the American one, as that's what
+    /// CMUdict describes.
+    pub fn with_accent(mut self, accent: Accent) -> Self {
+        self.accent = accent;
+        self
+    }
+
     pub fn look_up(&self, word: &str) -> Option<impl Iterator<Item = char> + Clone + 'txt> {
         let arpa_chars = self.map.get(&word.into())?;
         Some(
             arpa_chars
                 .split_whitespace()
-                .flat_map(|c| map_char_to_ipa(c).chars()),
+                .filter_map(map_char_to_ipa)
+                .flat_map(str::chars),
         )
     }
+
+    /// Looks up the pronunciation of the word in IPA, in the given accent.
+    pub fn look_up_accent(&self, word: &str, accent: Accent) -> Option<String> {
+        match accent {
+            Accent::American => Some(self.look_up(word)?.collect()),
+            Accent::British => Some(to_british_ipa(self.map.get(&word.into())?)),
+        }
+    }
+
+    /// Looks up all the pronunciations of the word in IPA, in the given
+    /// accent. CMUdict lists alternative pronunciations as `WORD(1)`,
+    /// `WORD(2)` and so on.
+    pub fn look_up_all_accent(&self, word: &str, accent: Accent) -> Vec<String> {
+        let variants = (1..).map(|i| format!("{}({})", word, i));
+        iter::once(word.to_owned())
+            .chain(variants)
+            .map(|word| self.look_up_accent(&word, accent))
+            .take_while(Option::is_some)
+            .flatten()
+            .collect()
+    }
+}
+
+/// CMUdict only covers (American) English.
+impl PronunciationSource for Dictionary<'_> {
+    fn name(&self) -> &str {
+        "CMUdict"
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        if language != "en" {
+            return Ok(None);
+        }
+        Ok(self
+            .look_up_accent(word, self.accent)
+            .map(|ipa| Pronunciation {
+                word: word.to_owned(),
+                ipa,
+                part_of_speech: None,
+            }))
+    }
+
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        if language != "en" {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .look_up_all_accent(word, self.accent)
+            .into_iter()
+            .map(|ipa| Pronunciation {
+                word: word.to_owned(),
+                ipa,
+                part_of_speech: None,
+            })
+            .collect())
+    }
 }
 
 pub const CMUDICT_07B: &str = include_str!("cmudict-0.7b.txt");
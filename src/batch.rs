@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use crate::{
+    ipa_to_hangul::{self, Options},
+    pronunciation_source::{Pronunciation, Resolver},
+};
+
+/// How looking up a word went.
+pub enum Outcome {
+    Found {
+        source: String,
+        pronunciations: Vec<Pronunciation>,
+    },
+    NotFound,
+    Failed(anyhow::Error),
+}
+
+/// A pronunciation of a word, along with its 한글 and the source it came
+/// from.
+pub struct Transcription {
+    pub source: String,
+    pub ipa: String,
+    pub hangul: String,
+}
+
+impl Outcome {
+    /// Transcribes the first pronunciation that was found, or all of them.
+    pub fn transcriptions(&self, all: bool, options: Options) -> Vec<Transcription> {
+        let (source, pronunciations) = match self {
+            Outcome::Found {
+                source,
+                pronunciations,
+            } => (source, pronunciations),
+            Outcome::NotFound | Outcome::Failed(_) => return Vec::new(),
+        };
+
+        let count = if all { pronunciations.len() } else { 1 };
+        pronunciations
+            .iter()
+            .take(count)
+            .map(|pronunciation| Transcription {
+                source: source.clone(),
+                ipa: pronunciation.ipa.clone(),
+                hangul: ipa_to_hangul::convert_with(
+                    &mut Default::default(),
+                    pronunciation.ipa.chars(),
+                    options,
+                )
+                .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Reads a list of words, one per line. Empty lines are skipped.
+pub fn read_word_list(txt: &str) -> Vec<String> {
+    txt.lines()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Looks up all the words in the sources of the resolver. A word that none
+/// of them know is not found, even if a source failed, so that it can still
+/// be looked up elsewhere.
+pub fn resolve(resolver: &Resolver<'_>, words: &[String], language: &str) -> Vec<Outcome> {
+    words
+        .iter()
+        .map(|word| match resolver.resolve_all(word, language) {
+            Ok(resolutions) => Outcome::Found {
+                source: resolutions.source.to_owned(),
+                pronunciations: resolutions.pronunciations,
+            },
+            Err(_) => Outcome::NotFound,
+        })
+        .collect()
+}
+
+/// Splits the text into words and the punctuation and whitespace between
+/// them. Apostrophes within words, like in `don't`, are part of them.
+fn tokens(text: &str) -> Vec<(&str, bool)> {
+    let chars = text.char_indices().map(|(_, c)| c).collect::<Vec<_>>();
+    let offsets = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+    let is_word = |i: usize| {
+        chars[i].is_alphanumeric()
+            || (chars[i] == '\''
+                && i > 0
+                && chars[i - 1].is_alphanumeric()
+                && matches!(chars.get(i + 1), Some(c) if c.is_alphanumeric()))
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for i in 1..=chars.len() {
+        if i == chars.len() || is_word(i) != is_word(start) {
+            let end = offsets.get(i).copied().unwrap_or(text.len());
+            tokens.push((&text[offsets[start]..end], is_word(start)));
+            start = i;
+        }
+    }
+    tokens
+}
+
+/// The distinct words of the texts, in the order they first appear.
+pub fn words<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut seen = HashSet::new();
+    for text in texts {
+        for (word, is_word) in tokens(text) {
+            if is_word && seen.insert(word.to_lowercase()) {
+                words.push(word.to_owned());
+            }
+        }
+    }
+    words
+}
+
+/// Transcribes the text word by word, into its pronunciation and its 한글.
+/// Punctuation is kept, as are the words without a transcription.
+pub fn transcribe_text<'a>(
+    text: &str,
+    mut transcribe: impl FnMut(&str) -> Option<&'a Transcription>,
+) -> (String, String) {
+    let mut ipa = String::new();
+    let mut hangul = String::new();
+
+    for (token, is_word) in tokens(text) {
+        let transcription = if is_word { transcribe(token) } else { None };
+        match transcription {
+            Some(transcription) => {
+                ipa.push_str(&transcription.ipa);
+                hangul.push_str(&transcription.hangul);
+            }
+            None => {
+                ipa.push_str(token);
+                hangul.push_str(token);
+            }
+        }
+    }
+
+    (ipa, hangul)
+}
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// The kinds of files that can be transcribed as a whole.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Plain text, where every line is transcribed.
+    Text,
+    /// Comma separated values, where the first column is transcribed.
+    Csv,
+    /// SubRip subtitles, where the text of every subtitle is transcribed.
+    Subtitles,
+}
+
+impl Format {
+    /// Determines the format from the extension of the file. Unknown files
+    /// are treated as plain text.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => Format::Csv,
+            Some("srt") => Format::Subtitles,
+            _ => Format::Text,
+        }
+    }
+}
+
+/// A file split into rows of fields, one of which holds the text to
+/// transcribe.
+pub struct Document {
+    rows: Vec<Vec<String>>,
+    text_column: usize,
+    header: bool,
+}
+
+fn parse_csv(txt: &str) -> Result<Vec<Vec<String>>> {
+    let txt = txt.trim_start_matches('\u{feff}');
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = txt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        bail!("A quoted field is never closed.");
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_subtitles(txt: &str) -> Result<Vec<Vec<String>>> {
+    let txt = txt.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    txt.split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .enumerate()
+        .map(|(index, block)| {
+            let mut lines = block.lines();
+            let number = lines.next().unwrap_or_default();
+            let timing = lines
+                .next()
+                .filter(|t| t.contains("-->"))
+                .with_context(|| format!("Subtitle {} has no timing.", index + 1))?;
+            let text = lines.collect::<Vec<_>>().join(" ");
+            Ok(vec![number.to_owned(), timing.to_owned(), text])
+        })
+        .collect()
+}
+
+impl Document {
+    pub fn parse(format: Format, txt: &str) -> Result<Self> {
+        Ok(match format {
+            Format::Text => Self {
+                rows: txt.lines().map(|l| vec![l.to_owned()]).collect(),
+                text_column: 0,
+                header: false,
+            },
+            Format::Csv => Self {
+                rows: parse_csv(txt).context("Failed parsing the CSV file.")?,
+                text_column: 0,
+                header: false,
+            },
+            Format::Subtitles => Self {
+                rows: parse_subtitles(txt).context("Failed parsing the subtitles.")?,
+                text_column: 2,
+                header: false,
+            },
+        })
+    }
+
+    /// Sets whether the first row is a header, such as the column names of a
+    /// spreadsheet export. The header isn't transcribed, but gets the names
+    /// of the added columns instead.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// The texts to transcribe, one per row, leaving out the header.
+    pub fn texts(&self) -> impl Iterator<Item = &str> + '_ {
+        self.rows
+            .iter()
+            .skip(self.header as usize)
+            .map(move |row| row.get(self.text_column).map_or("", |t| &**t))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Adds the pronunciation and the 한글 of each row's text as two more
+    /// columns, named `ipa` and `hangul` in the header.
+    pub fn transcribe(
+        &self,
+        mut transcribe: impl FnMut(&str) -> (String, String),
+    ) -> Vec<Vec<String>> {
+        let header = self.rows.iter().take(self.header as usize).map(|row| {
+            let mut row = row.clone();
+            row.push("ipa".to_owned());
+            row.push("hangul".to_owned());
+            row
+        });
+        let rows = self
+            .rows
+            .iter()
+            .skip(self.header as usize)
+            .zip(self.texts())
+            .map(|(row, text)| {
+                let (ipa, hangul) = transcribe(text);
+                let mut row = row.clone();
+                row.push(ipa);
+                row.push(hangul);
+                row
+            });
+        header.chain(rows).collect()
+    }
+}
+
+/// Writes the rows as comma separated values.
+pub fn to_csv(rows: &[Vec<String>]) -> String {
+    let mut csv = String::new();
+    for row in rows {
+        for (i, field) in row.iter().enumerate() {
+            if i != 0 {
+                csv.push(',');
+            }
+            if field.contains(&[',', '"', '\n', '\r'][..]) {
+                csv.push('"');
+                csv.push_str(&field.replace('"', "\"\""));
+                csv.push('"');
+            } else {
+                csv.push_str(field);
+            }
+        }
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Writes the rows as lines of tab separated values.
+pub fn to_text(rows: &[Vec<String>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let fields = row
+            .iter()
+            .map(|f| f.replace(&['\t', '\n', '\r'][..], " "))
+            .collect::<Vec<_>>();
+        text.push_str(&fields.join("\t"));
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,421 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    hangul_builder::{self, Syllable},
+    ipa_to_hangul::{self, Options, Step},
+};
+
+/// English loanwords along with their spelling according to the Korean
+/// loanword orthography.
+pub const LOANWORDS: &str = include_str!("loanwords.tsv");
+
+/// A word along with its accepted spelling in 한글.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub word: String,
+    pub hangul: String,
+}
+
+/// Parses a corpus with one word and its spelling in 한글 per line, separated
+/// by a tab. Empty lines and lines starting with `#` are ignored.
+pub fn parse_corpus(txt: &str) -> Result<Vec<Sample>> {
+    txt.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            let (word, hangul) = line
+                .split_once('\t')
+                .with_context(|| format!("Line {} of the corpus has no spelling.", index + 1))?;
+            Ok(Sample {
+                word: word.trim().to_owned(),
+                hangul: hangul.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// A jamo that was transcribed as a different one. `None` stands for a jamo
+/// that is missing on that side, i.e. an insertion or a deletion.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Confusion {
+    pub expected: Option<char>,
+    pub actual: Option<char>,
+    pub count: usize,
+}
+
+/// A difference between the expected and the actual spelling, attributed to
+/// the step of the transcription that caused it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    /// The index of the step in [`Outcome::steps`].
+    pub step: usize,
+    pub expected: Option<char>,
+    pub actual: Option<char>,
+}
+
+/// How a single word was transcribed.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub word: String,
+    pub ipa: String,
+    pub expected: String,
+    pub actual: String,
+    pub steps: Vec<Step>,
+    pub errors: Vec<Error>,
+}
+
+impl Outcome {
+    pub fn is_correct(&self) -> bool {
+        self.expected == self.actual
+    }
+
+    /// The edit distance between the expected and the actual spelling, in
+    /// jamo.
+    pub fn distance(&self) -> usize {
+        self.errors.len()
+    }
+}
+
+/// How often an IPA symbol was transcribed wrongly and what it was confused
+/// with.
+#[derive(Clone, Debug)]
+pub struct SymbolStats {
+    pub symbol: String,
+    pub occurrences: usize,
+    /// How many of the occurrences caused at least one wrong jamo.
+    pub errors: usize,
+    pub confusions: Vec<Confusion>,
+}
+
+impl SymbolStats {
+    pub fn error_rate(&self) -> f32 {
+        self.errors as f32 / self.occurrences.max(1) as f32
+    }
+}
+
+/// The results of transcribing a corpus.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub outcomes: Vec<Outcome>,
+    /// The words whose pronunciation couldn't be looked up.
+    pub missing: Vec<String>,
+}
+
+/// Splits the 한글 into compatibility jamo. Anything else is kept as is.
+fn jamo(hangul: &str) -> Vec<char> {
+    hangul
+        .chars()
+        .flat_map(|c| match Syllable::from_char(c) {
+            Some(syllable) => syllable.compatibility_jamo().collect::<Vec<_>>(),
+            None => vec![c],
+        })
+        .collect()
+}
+
+/// Aligns the two sequences with the smallest amount of edits. Each pair
+/// holds the indices of the elements that are aligned with each other, with
+/// `None` for an insertion or a deletion.
+fn align(a: &[char], b: &[char]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (n, m) = (a.len(), b.len());
+    let mut distances = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitution = (a[i - 1] != b[j - 1]) as usize;
+            distances[i][j] = (distances[i - 1][j - 1] + substitution)
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0
+            && j > 0
+            && distances[i][j] == distances[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as usize
+        {
+            pairs.push((Some(i - 1), Some(j - 1)));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && distances[i][j] == distances[i - 1][j] + 1 {
+            pairs.push((Some(i - 1), None));
+            i -= 1;
+        } else {
+            pairs.push((None, Some(j - 1)));
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Finds the step that produced each jamo of the actual spelling. The jamo
+/// the builder added on its own are attributed to the step before them.
+fn owners(steps: &[Step], actual: &[char]) -> Vec<usize> {
+    let mut intended = Vec::new();
+    let mut intended_owners = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        for c in step.jamo.chars() {
+            intended.push(c);
+            intended_owners.push(index);
+        }
+    }
+
+    let mut owners = vec![None; actual.len()];
+    for (i, j) in align(&intended, actual) {
+        if let (Some(i), Some(j)) = (i, j) {
+            owners[j] = Some(intended_owners[i]);
+        }
+    }
+
+    let mut previous = owners.iter().flatten().next().copied().unwrap_or(0);
+    owners
+        .into_iter()
+        .map(|owner| {
+            previous = owner.unwrap_or(previous);
+            previous
+        })
+        .collect()
+}
+
+/// Finds the differences between the spellings and the steps that caused
+/// them. A missing jamo is attributed to the step that produced the jamo
+/// before it.
+fn errors(steps: &[Step], expected: &[char], actual: &[char]) -> Vec<Error> {
+    let owners = owners(steps, actual);
+    let mut errors = Vec::new();
+    let mut previous = owners.first().copied().unwrap_or(0);
+
+    for (i, j) in align(expected, actual) {
+        let step = j.map_or(previous, |j| owners[j]);
+        previous = step;
+
+        let expected = i.map(|i| expected[i]);
+        let actual = j.map(|j| actual[j]);
+        if expected != actual {
+            errors.push(Error {
+                step,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    errors
+}
+
+fn count_confusions<'a>(errors: impl Iterator<Item = &'a Error>) -> Vec<Confusion> {
+    let mut counts = HashMap::new();
+    for error in errors {
+        *counts.entry((error.expected, error.actual)).or_insert(0) += 1;
+    }
+
+    let mut confusions = counts
+        .into_iter()
+        .map(|((expected, actual), count)| Confusion {
+            expected,
+            actual,
+            count,
+        })
+        .collect::<Vec<_>>();
+    confusions.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(a.expected.cmp(&b.expected))
+            .then(a.actual.cmp(&b.actual))
+    });
+    confusions
+}
+
+/// Transcribes each word of the corpus and compares it to its accepted
+/// spelling. The pronunciations are looked up with the closure, which returns
+/// `None` if it doesn't know the word.
+pub fn evaluate(
+    corpus: &[Sample],
+    options: Options,
+    mut look_up: impl FnMut(&str) -> Option<String>,
+) -> Report {
+    let mut builder = hangul_builder::Builder::new();
+    let mut report = Report::default();
+
+    for sample in corpus {
+        let ipa = match look_up(&sample.word) {
+            Some(ipa) => ipa,
+            None => {
+                report.missing.push(sample.word.clone());
+                continue;
+            }
+        };
+        let steps = ipa_to_hangul::trace(&mut builder, ipa.chars(), options);
+        let actual = builder.finish().collect::<String>();
+        let errors = errors(&steps, &jamo(&sample.hangul), &jamo(&actual));
+
+        report.outcomes.push(Outcome {
+            word: sample.word.clone(),
+            ipa,
+            expected: sample.hangul.clone(),
+            actual,
+            steps,
+            errors,
+        });
+    }
+
+    report
+}
+
+impl Report {
+    /// The share of the words that were transcribed exactly as expected.
+    pub fn accuracy(&self) -> f32 {
+        let correct = self.outcomes.iter().filter(|o| o.is_correct()).count();
+        correct as f32 / self.outcomes.len().max(1) as f32
+    }
+
+    /// The average edit distance per word, in jamo.
+    pub fn mean_distance(&self) -> f32 {
+        let total = self.outcomes.iter().map(|o| o.distance()).sum::<usize>();
+        total as f32 / self.outcomes.len().max(1) as f32
+    }
+
+    /// Lists the IPA symbols by how often they were transcribed wrongly,
+    /// starting with the one causing the most errors.
+    pub fn symbols(&self) -> Vec<SymbolStats> {
+        let mut symbols = HashMap::<&str, (usize, usize, Vec<&Error>)>::new();
+
+        for outcome in &self.outcomes {
+            for (index, step) in outcome.steps.iter().enumerate() {
+                let (occurrences, errors, confusions) = symbols.entry(&step.ipa).or_default();
+                let len = confusions.len();
+                confusions.extend(outcome.errors.iter().filter(|e| e.step == index));
+                *occurrences += 1;
+                *errors += (confusions.len() != len) as usize;
+            }
+        }
+
+        let mut symbols = symbols
+            .into_iter()
+            .map(|(symbol, (occurrences, errors, confusions))| SymbolStats {
+                symbol: symbol.to_owned(),
+                occurrences,
+                errors,
+                confusions: count_confusions(confusions.into_iter()),
+            })
+            .collect::<Vec<_>>();
+        symbols.sort_by(|a, b| {
+            b.errors
+                .cmp(&a.errors)
+                .then(b.occurrences.cmp(&a.occurrences))
+                .then(a.symbol.cmp(&b.symbol))
+        });
+        symbols
+    }
+
+    /// Lists the jamo that were confused with each other, starting with the
+    /// most frequent confusion.
+    pub fn confusions(&self) -> Vec<Confusion> {
+        count_confusions(self.outcomes.iter().flat_map(|o| &o.errors))
+    }
+}
+
+fn fmt_jamo(jamo: Option<char>) -> char {
+    jamo.unwrap_or('∅')
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let correct = self.outcomes.iter().filter(|o| o.is_correct()).count();
+        writeln!(
+            f,
+            "Accuracy: {:.1}% ({} of {})",
+            100.0 * self.accuracy(),
+            correct,
+            self.outcomes.len()
+        )?;
+        writeln!(f, "Mean jamo edit distance: {:.2}", self.mean_distance())?;
+        if !self.missing.is_empty() {
+            writeln!(f, "Missing pronunciations: {}", self.missing.join(", "))?;
+        }
+
+        writeln!(f, "Errors per IPA symbol:")?;
+        for stats in self.symbols().iter().filter(|s| s.errors != 0) {
+            let confusions = stats
+                .confusions
+                .iter()
+                .take(3)
+                .map(|c| {
+                    format!(
+                        "{} → {} ({})",
+                        fmt_jamo(c.expected),
+                        fmt_jamo(c.actual),
+                        c.count
+                    )
+                })
+                .collect::<Vec<_>>();
+            writeln!(
+                f,
+                "  {} {:5.1}% ({} of {}): {}",
+                stats.symbol,
+                100.0 * stats.error_rate(),
+                stats.errors,
+                stats.occurrences,
+                confusions.join(", ")
+            )?;
+        }
+
+        writeln!(f, "Most frequent jamo confusions:")?;
+        for confusion in self.confusions().iter().take(20) {
+            writeln!(
+                f,
+                "  {} → {} ({})",
+                fmt_jamo(confusion.expected),
+                fmt_jamo(confusion.actual),
+                confusion.count
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_errors_to_the_symbols() {
+        let corpus = [Sample {
+            word: "computer".to_owned(),
+            hangul: "컴퓨터".to_owned(),
+        }];
+        let report = evaluate(&corpus, Options::default(), |_| Some("kʌmpjutɝ".to_owned()));
+
+        let outcome = &report.outcomes[0];
+        assert_eq!(outcome.actual, "검퓨트");
+        let errors = outcome
+            .errors
+            .iter()
+            .map(|e| (&*outcome.steps[e.step].ipa, e.expected, e.actual))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [("k", Some('ㅋ'), Some('ㄱ')), ("ɝ", Some('ㅓ'), Some('ㅡ'))]
+        );
+    }
+
+    #[test]
+    fn parses_the_bundled_corpus() {
+        let corpus = parse_corpus(LOANWORDS).unwrap();
+        assert!(corpus.len() > 100);
+        assert!(corpus
+            .iter()
+            .all(|s| !s.word.is_empty() && !s.hangul.is_empty()));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::hangul_to_ipa;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Manner {
+    Stop,
+    Affricate,
+    Fricative,
+    Nasal,
+    Lateral,
+    Rhotic,
+    Glide,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Laryngeal {
+    Plain,
+    Aspirated,
+    Tense,
+}
+
+#[derive(Copy, Clone)]
+enum Phone {
+    Consonant {
+        /// From bilabial (0) to glottal (7).
+        place: u8,
+        manner: Manner,
+        voiced: bool,
+    },
+    Vowel {
+        /// From close (0) to open (1).
+        height: f32,
+        /// From front (0) to back (1).
+        backness: f32,
+        rounded: bool,
+    },
+}
+
+#[derive(Copy, Clone)]
+struct Segment {
+    phone: Phone,
+    laryngeal: Laryngeal,
+}
+
+fn consonant(place: u8, manner: Manner, voiced: bool) -> Phone {
+    Phone::Consonant {
+        place,
+        manner,
+        voiced,
+    }
+}
+
+fn vowel(height: f32, backness: f32, rounded: bool) -> Phone {
+    Phone::Vowel {
+        height,
+        backness,
+        rounded,
+    }
+}
+
+fn affricate(first: char, second: char) -> Option<Phone> {
+    Some(match (first, second) {
+        ('t', 'ʃ') | ('t', 'ɕ') => consonant(4, Manner::Affricate, false),
+        ('d', 'ʒ') | ('d', 'ʑ') => consonant(4, Manner::Affricate, true),
+        ('t', 's') => consonant(3, Manner::Affricate, false),
+        ('d', 'z') => consonant(3, Manner::Affricate, true),
+        _ => return None,
+    })
+}
+
+fn phone(c: char) -> Option<Phone> {
+    use Manner::*;
+
+    Some(match c {
+        'p' => consonant(0, Stop, false),
+        'b' => consonant(0, Stop, true),
+        'm' => consonant(0, Nasal, true),
+        'w' => consonant(0, Glide, true),
+        'f' => consonant(1, Fricative, false),
+        'v' => consonant(1, Fricative, true),
+        'θ' => consonant(2, Fricative, false),
+        'ð' => consonant(2, Fricative, true),
+        't' => consonant(3, Stop, false),
+        'd' => consonant(3, Stop, true),
+        'n' => consonant(3, Nasal, true),
+        's' => consonant(3, Fricative, false),
+        'z' => consonant(3, Fricative, true),
+        'ʦ' => consonant(3, Affricate, false),
+        'ʧ' => consonant(4, Affricate, false),
+        'ʤ' => consonant(4, Affricate, true),
+        'l' => consonant(3, Lateral, true),
+        'ɹ' | 'ɾ' | 'r' => consonant(3, Rhotic, true),
+        'ʃ' | 'ɕ' => consonant(4, Fricative, false),
+        'ʒ' | 'ʑ' => consonant(4, Fricative, true),
+        'ç' => consonant(5, Fricative, false),
+        'j' => consonant(5, Glide, true),
+        'k' => consonant(6, Stop, false),
+        'ɡ' | 'g' => consonant(6, Stop, true),
+        'ŋ' => consonant(6, Nasal, true),
+        'x' => consonant(6, Fricative, false),
+        'ɰ' => consonant(6, Glide, true),
+        'h' => consonant(7, Fricative, false),
+        'ɦ' => consonant(7, Fricative, true),
+        'ʔ' => consonant(7, Stop, false),
+        'i' => vowel(0.0, 0.0, false),
+        'y' => vowel(0.0, 0.0, true),
+        'ɪ' => vowel(0.15, 0.1, false),
+        'e' => vowel(0.35, 0.0, false),
+        'ø' => vowel(0.35, 0.0, true),
+        'ɛ' => vowel(0.65, 0.0, false),
+        'œ' => vowel(0.65, 0.0, true),
+        'æ' => vowel(0.85, 0.0, false),
+        'a' => vowel(1.0, 0.3, false),
+        'ɐ' => vowel(0.8, 0.5, false),
+        'ɑ' => vowel(1.0, 1.0, false),
+        'ɒ' => vowel(1.0, 1.0, true),
+        'ɔ' => vowel(0.65, 1.0, true),
+        'o' => vowel(0.35, 1.0, true),
+        'ʊ' => vowel(0.15, 0.9, true),
+        'u' => vowel(0.0, 1.0, true),
+        'ɯ' => vowel(0.0, 1.0, false),
+        'ɨ' => vowel(0.0, 0.5, false),
+        'ə' => vowel(0.5, 0.5, false),
+        'ɜ' | 'ɝ' | 'ɚ' => vowel(0.6, 0.5, false),
+        'ʌ' => vowel(0.65, 0.9, false),
+        _ => return None,
+    })
+}
+
+/// Splits IPA into its segments. Diacritics are attached to the segment
+/// they modify, affricates are kept together and anything that isn't a
+/// known sound, such as stress marks and spaces, is skipped.
+fn segments(ipa: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut previous_base = None;
+
+    for c in ipa.chars() {
+        match c {
+            'ʰ' => {
+                if let Some(last) = segments.last_mut() {
+                    last.laryngeal = Laryngeal::Aspirated;
+                }
+            }
+            '\u{348}' => {
+                if let Some(last) = segments.last_mut() {
+                    last.laryngeal = Laryngeal::Tense;
+                }
+            }
+            '\u{300}'..='\u{36F}' | 'ː' | 'ˑ' | 'ʷ' | 'ʲ' => {}
+            c => {
+                if let Some(affricate) = previous_base.and_then(|p| affricate(p, c)) {
+                    if let Some(last) = segments.last_mut() {
+                        last.phone = affricate;
+                    }
+                    previous_base = None;
+                } else if let Some(phone) = phone(c) {
+                    segments.push(Segment {
+                        phone,
+                        laryngeal: Laryngeal::Plain,
+                    });
+                    previous_base = Some(c);
+                } else {
+                    previous_base = None;
+                }
+            }
+        }
+    }
+
+    segments
+}
+
+fn manner_distance(a: Manner, b: Manner) -> f32 {
+    use Manner::*;
+
+    match (a, b) {
+        _ if a == b => 0.0,
+        (Stop, Affricate) | (Affricate, Stop) => 0.3,
+        (Affricate, Fricative) | (Fricative, Affricate) => 0.3,
+        (Lateral, Rhotic) | (Rhotic, Lateral) => 0.2,
+        (Stop, Fricative) | (Fricative, Stop) => 0.5,
+        (Glide, Rhotic) | (Rhotic, Glide) => 0.5,
+        _ => 0.7,
+    }
+}
+
+fn substitution_cost(a: Segment, b: Segment) -> f32 {
+    let cost = match (a.phone, b.phone) {
+        (
+            Phone::Consonant {
+                place: place_a,
+                manner: manner_a,
+                voiced: voiced_a,
+            },
+            Phone::Consonant {
+                place: place_b,
+                manner: manner_b,
+                voiced: voiced_b,
+            },
+        ) => {
+            manner_distance(manner_a, manner_b)
+                + 0.5 * (place_a as f32 - place_b as f32).abs() / 7.0
+                + if voiced_a != voiced_b { 0.15 } else { 0.0 }
+                + if a.laryngeal != b.laryngeal { 0.1 } else { 0.0 }
+        }
+        (
+            Phone::Vowel {
+                height: height_a,
+                backness: backness_a,
+                rounded: rounded_a,
+            },
+            Phone::Vowel {
+                height: height_b,
+                backness: backness_b,
+                rounded: rounded_b,
+            },
+        ) => {
+            0.6 * (height_a - height_b).abs()
+                + 0.4 * (backness_a - backness_b).abs()
+                + if rounded_a != rounded_b { 0.25 } else { 0.0 }
+        }
+        (
+            Phone::Consonant {
+                manner: Manner::Glide,
+                place,
+                ..
+            },
+            Phone::Vowel {
+                height, backness, ..
+            },
+        )
+        | (
+            Phone::Vowel {
+                height, backness, ..
+            },
+            Phone::Consonant {
+                manner: Manner::Glide,
+                place,
+                ..
+            },
+        ) => {
+            // Glides are close to the close vowel they are made of.
+            let glide_backness = if place == 5 { 0.0 } else { 1.0 };
+            0.4 + 0.6 * height + 0.4 * (backness - glide_backness).abs()
+        }
+        _ => 1.0,
+    };
+    cost.min(1.0)
+}
+
+fn indel_cost(segment: Segment) -> f32 {
+    match segment.phone {
+        // Inserting a weak unrounded vowel is how Korean breaks up consonant
+        // clusters, so it's less severe than losing or adding other sounds.
+        Phone::Vowel {
+            height,
+            backness,
+            rounded: false,
+        } if height <= 0.5 && backness >= 0.5 => 0.5,
+        _ => 1.0,
+    }
+}
+
+/// Calculates the phonetic distance between two IPA strings. This is an edit
+/// distance where substituting a sound costs less the more phonetic features
+/// the two sounds share.
+pub fn distance(a: &str, b: &str) -> f32 {
+    let a = segments(a);
+    let b = segments(b);
+    aligned_distance(&a, &b)
+}
+
+fn aligned_distance(a: &[Segment], b: &[Segment]) -> f32 {
+    let mut row: Vec<f32> = Vec::with_capacity(b.len() + 1);
+    row.push(0.0);
+    for &segment in b {
+        let last = row[row.len() - 1];
+        row.push(last + indel_cost(segment));
+    }
+
+    for &segment_a in a {
+        let mut diagonal = row[0];
+        row[0] += indel_cost(segment_a);
+        for (j, &segment_b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            let value = (diagonal + substitution_cost(segment_a, segment_b))
+                .min(above + indel_cost(segment_a))
+                .min(row[j] + indel_cost(segment_b));
+            diagonal = above;
+            row[j + 1] = value;
+        }
+    }
+
+    row[b.len()]
+}
+
+fn aligned_similarity(a: &[Segment], b: &[Segment]) -> f32 {
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 1.0;
+    }
+    (1.0 - aligned_distance(a, b) / len as f32).max(0.0)
+}
+
+/// Calculates how similar two IPA strings sound, from 0 (nothing in common)
+/// to 1 (identical).
+pub fn similarity(a: &str, b: &str) -> f32 {
+    aligned_similarity(&segments(a), &segments(b))
+}
+
+/// Scores how faithfully the 한글 reproduces the original pronunciation, from
+/// 0 to 1. The 한글 is read back the way a Korean reader would pronounce it
+/// and compared with the original IPA.
+pub fn score(ipa: &str, hangul: &str) -> f32 {
+    similarity(ipa, &hangul_to_ipa::convert(hangul))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn identical_pronunciations_have_no_distance() {
+        assert_close(distance("kæt", "kæt"), 0.0);
+        assert_close(distance("", ""), 0.0);
+        // Stress and length marks are ignored and affricates are one sound.
+        assert_close(distance("ˈkɑːt", "kɑt"), 0.0);
+        assert_close(distance("tʃɪp", "ʧɪp"), 0.0);
+        assert_close(similarity("kæt", "kæt"), 1.0);
+        assert_close(similarity("", ""), 1.0);
+    }
+
+    #[test]
+    fn substitutions_cost_less_than_insertions_and_deletions() {
+        // Only the voicing differs.
+        assert_close(distance("pæt", "bæt"), 0.15);
+        // Only the aspiration differs.
+        assert_close(distance("tʰa", "ta"), 0.1);
+        // A lateral for a rhotic.
+        assert_close(distance("ɹa", "la"), 0.2);
+        assert_close(distance("pæt", "æt"), 1.0);
+        assert_close(distance("kæt", "kætn"), 1.0);
+        // A t followed by s becomes the affricate ʦ instead.
+        assert_close(distance("kæt", "kæts"), 0.3);
+        assert!(distance("pæt", "bæt") < distance("pæt", "æt"));
+        assert_close(similarity("pæt", "bæt"), 0.95);
+        assert_close(similarity("kæt", ""), 0.0);
+    }
+
+    #[test]
+    fn inserting_an_epenthetic_vowel_costs_half() {
+        assert_close(distance("sk", "sɯk"), 0.5);
+        assert_close(distance("sk", "sak"), 1.0);
+    }
+
+    #[test]
+    fn scores_the_hangul_against_the_pronunciation() {
+        assert!(score("kæt", "캣") > score("kæt", "컷"));
+        assert!(score("kæt", "캣") > score("kæt", "캐트"));
+    }
+}
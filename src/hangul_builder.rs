@@ -1,6 +1,8 @@
-use unicode_normalization::UnicodeNormalization;
+use std::fmt;
 
-#[derive(Copy, Clone)]
+use crate::syllabary::SyllabaryBuilder;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Consonant {
     /// ㅂ
     B,
@@ -36,9 +38,29 @@ pub enum Consonant {
     Gg,
     /// ㄸ
     Dd,
+    /// ㅆ
+    Ss,
+    /// ㅉ
+    Jj,
+}
+
+impl Consonant {
+    /// All the consonants, in the order of the initial jamo in Unicode.
+    pub const ALL: [Consonant; 19] = INITIALS;
+
+    /// Whether the consonant can close a syllable. ㅃ, ㄸ and ㅉ can only
+    /// start one.
+    pub fn can_be_final(self) -> bool {
+        !matches!(self, Consonant::Bb | Consonant::Dd | Consonant::Jj)
+    }
+
+    /// The consonant as a compatibility jamo, such as ㄱ.
+    pub fn compatibility_jamo(self) -> char {
+        COMPATIBILITY_INITIALS[initial_index(self) as usize]
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Vowel {
     /// ㅐ
     Ae,
@@ -78,9 +100,23 @@ pub enum Vowel {
     Yo,
     /// ㅠ
     Yu,
+    /// ㅚ
+    Oe,
+    /// ㅢ
+    Ui,
+}
+
+impl Vowel {
+    /// All the vowels, in the order of the jamo in Unicode.
+    pub const ALL: [Vowel; 21] = VOWELS;
+
+    /// The vowel as a compatibility jamo, such as ㅏ.
+    pub fn compatibility_jamo(self) -> char {
+        std::char::from_u32(0x314F + vowel_index(self)).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Position {
     InitialConsonant,
     Vowel,
@@ -99,17 +135,323 @@ impl Position {
     }
 }
 
+/// Where the builder is within the current syllable.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct State {
+    pub position: Position,
+    /// The consonant that waits for what follows to decide whether it closes
+    /// the current syllable or starts the next one.
+    pub buffered_consonant: Option<Consonant>,
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.position)?;
+        if let Some(cons) = self.buffered_consonant {
+            write!(f, " ({} buffered)", cons.compatibility_jamo())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The consonant can't be placed in the final consonant position.
+    InvalidFinalConsonant(Consonant),
+    /// The two consonants don't form a compound final consonant.
+    InvalidFinalCluster(Consonant, Consonant),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidFinalConsonant(cons) => {
+                write!(f, "{:?} can't be in final consonant position", cons)
+            }
+            Error::InvalidFinalCluster(first, second) => write!(
+                f,
+                "{:?} and {:?} don't form a compound final consonant",
+                first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+const SYLLABLE_BASE: u32 = 0xAC00;
+const SYLLABLE_COUNT: u32 = 11172;
+const VOWEL_COUNT: u32 = 21;
+const FINAL_COUNT: u32 = 28;
+
+/// The consonants in the order of the initial jamo in Unicode.
+const INITIALS: [Consonant; 19] = [
+    Consonant::G,
+    Consonant::Gg,
+    Consonant::N,
+    Consonant::D,
+    Consonant::Dd,
+    Consonant::L,
+    Consonant::M,
+    Consonant::B,
+    Consonant::Bb,
+    Consonant::S,
+    Consonant::Ss,
+    Consonant::Ng,
+    Consonant::J,
+    Consonant::Jj,
+    Consonant::Ch,
+    Consonant::K,
+    Consonant::T,
+    Consonant::P,
+    Consonant::H,
+];
+
+/// The vowels in the order of the medial jamo in Unicode.
+const VOWELS: [Vowel; 21] = [
+    Vowel::A,
+    Vowel::Ae,
+    Vowel::Ya,
+    Vowel::Yae,
+    Vowel::Eo,
+    Vowel::E,
+    Vowel::Yeo,
+    Vowel::Ye,
+    Vowel::O,
+    Vowel::Wa,
+    Vowel::Wae,
+    Vowel::Oe,
+    Vowel::Yo,
+    Vowel::U,
+    Vowel::Wo,
+    Vowel::We,
+    Vowel::Wi,
+    Vowel::Yu,
+    Vowel::Eu,
+    Vowel::Ui,
+    Vowel::I,
+];
+
+/// The final consonants in the order of the final jamo in Unicode.
+const FINALS: [FinalConsonant; 27] = [
+    FinalConsonant::Single(Consonant::G),
+    FinalConsonant::Single(Consonant::Gg),
+    FinalConsonant::Cluster(Consonant::G, Consonant::S),
+    FinalConsonant::Single(Consonant::N),
+    FinalConsonant::Cluster(Consonant::N, Consonant::J),
+    FinalConsonant::Cluster(Consonant::N, Consonant::H),
+    FinalConsonant::Single(Consonant::D),
+    FinalConsonant::Single(Consonant::L),
+    FinalConsonant::Cluster(Consonant::L, Consonant::G),
+    FinalConsonant::Cluster(Consonant::L, Consonant::M),
+    FinalConsonant::Cluster(Consonant::L, Consonant::B),
+    FinalConsonant::Cluster(Consonant::L, Consonant::S),
+    FinalConsonant::Cluster(Consonant::L, Consonant::T),
+    FinalConsonant::Cluster(Consonant::L, Consonant::P),
+    FinalConsonant::Cluster(Consonant::L, Consonant::H),
+    FinalConsonant::Single(Consonant::M),
+    FinalConsonant::Single(Consonant::B),
+    FinalConsonant::Cluster(Consonant::B, Consonant::S),
+    FinalConsonant::Single(Consonant::S),
+    FinalConsonant::Single(Consonant::Ss),
+    FinalConsonant::Single(Consonant::Ng),
+    FinalConsonant::Single(Consonant::J),
+    FinalConsonant::Single(Consonant::Ch),
+    FinalConsonant::Single(Consonant::K),
+    FinalConsonant::Single(Consonant::T),
+    FinalConsonant::Single(Consonant::P),
+    FinalConsonant::Single(Consonant::H),
+];
+
+/// The compatibility jamo of the consonants, in the same order as
+/// `INITIALS`.
+const COMPATIBILITY_INITIALS: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// The compatibility jamo of the final consonants, in the same order as
+/// `FINALS`.
+const COMPATIBILITY_FINALS: [char; 27] = [
+    'ㄱ', 'ㄲ', 'ㄳ', 'ㄴ', 'ㄵ', 'ㄶ', 'ㄷ', 'ㄹ', 'ㄺ', 'ㄻ', 'ㄼ', 'ㄽ', 'ㄾ', 'ㄿ', 'ㅀ', 'ㅁ',
+    'ㅂ', 'ㅄ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+fn initial_index(cons: Consonant) -> u32 {
+    INITIALS.iter().position(|&c| c == cons).unwrap_or_default() as u32
+}
+
+fn vowel_index(vowel: Vowel) -> u32 {
+    VOWELS.iter().position(|&v| v == vowel).unwrap_or_default() as u32
+}
+
+/// The index of the final consonant in Unicode, if it's a valid one. The
+/// index 0 is reserved for syllables without a final consonant.
+fn final_index(final_consonant: FinalConsonant) -> Option<u32> {
+    FINALS
+        .iter()
+        .position(|&f| f == final_consonant)
+        .map(|i| i as u32 + 1)
+}
+
+/// The consonant or consonant cluster that closes a syllable.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FinalConsonant {
+    Single(Consonant),
+    /// A compound final consonant, such as ㄺ for ㄹ and ㄱ.
+    Cluster(Consonant, Consonant),
+}
+
+/// A single 한글 syllable block.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Syllable {
+    initial_consonant: Consonant,
+    vowel: Vowel,
+    final_consonant: Option<FinalConsonant>,
+}
+
+impl Syllable {
+    /// Creates a syllable. A syllable starting with a vowel uses ㅇ as its
+    /// initial consonant.
+    pub fn new(
+        initial_consonant: Consonant,
+        vowel: Vowel,
+        final_consonant: Option<FinalConsonant>,
+    ) -> Result<Self, Error> {
+        if let Some(final_consonant) = final_consonant {
+            if final_index(final_consonant).is_none() {
+                return Err(match final_consonant {
+                    FinalConsonant::Single(cons) => Error::InvalidFinalConsonant(cons),
+                    FinalConsonant::Cluster(first, second) => {
+                        Error::InvalidFinalCluster(first, second)
+                    }
+                });
+            }
+        }
+        Ok(Self {
+            initial_consonant,
+            vowel,
+            final_consonant,
+        })
+    }
+
+    /// Decomposes a precomposed 한글 syllable.
+    pub fn from_char(c: char) -> Option<Self> {
+        let index = (c as u32).checked_sub(SYLLABLE_BASE)?;
+        if index >= SYLLABLE_COUNT {
+            return None;
+        }
+        let final_index = index % FINAL_COUNT;
+        Some(Self {
+            initial_consonant: INITIALS[(index / (VOWEL_COUNT * FINAL_COUNT)) as usize],
+            vowel: VOWELS[((index / FINAL_COUNT) % VOWEL_COUNT) as usize],
+            final_consonant: final_index.checked_sub(1).map(|i| FINALS[i as usize]),
+        })
+    }
+
+    pub fn initial_consonant(&self) -> Consonant {
+        self.initial_consonant
+    }
+
+    pub fn vowel(&self) -> Vowel {
+        self.vowel
+    }
+
+    pub fn final_consonant(&self) -> Option<FinalConsonant> {
+        self.final_consonant
+    }
+
+    fn final_index(&self) -> u32 {
+        self.final_consonant.and_then(final_index).unwrap_or(0)
+    }
+
+    /// The precomposed syllable, as it appears in NFC normalized text.
+    pub fn to_char(&self) -> char {
+        let index = (initial_index(self.initial_consonant) * VOWEL_COUNT + vowel_index(self.vowel))
+            * FINAL_COUNT
+            + self.final_index();
+        std::char::from_u32(SYLLABLE_BASE + index).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    /// The conjoining jamo of the syllable, as they appear in NFD normalized
+    /// text.
+    pub fn jamo(&self) -> impl Iterator<Item = char> {
+        let initial = std::char::from_u32(0x1100 + initial_index(self.initial_consonant));
+        let vowel = std::char::from_u32(0x1161 + vowel_index(self.vowel));
+        let final_consonant = match self.final_index() {
+            0 => None,
+            index => std::char::from_u32(0x11A7 + index),
+        };
+        initial.into_iter().chain(vowel).chain(final_consonant)
+    }
+
+    /// The compatibility jamo of the syllable, i.e. the letters as they are
+    /// typed on a keyboard. A silent ㅇ is left out.
+    pub fn compatibility_jamo(&self) -> impl Iterator<Item = char> {
+        let initial = match self.initial_consonant {
+            Consonant::Ng => None,
+            cons => Some(cons.compatibility_jamo()),
+        };
+        let vowel = self.vowel.compatibility_jamo();
+        let final_consonant = self
+            .final_index()
+            .checked_sub(1)
+            .map(|i| COMPATIBILITY_FINALS[i as usize]);
+        initial
+            .into_iter()
+            .chain(Some(vowel))
+            .chain(final_consonant)
+    }
+}
+
+/// A piece of the text produced by the builder.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Segment {
+    Syllable(Syllable),
+    Space,
+}
+
+/// The ways 한글 can be encoded as text.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Form {
+    /// Precomposed syllables.
+    Nfc,
+    /// Conjoining jamo.
+    Nfd,
+    /// Compatibility jamo, one letter after another.
+    CompatibilityJamo,
+}
+
+/// Renders the segments as text in the given form.
+pub fn render(segments: &[Segment], form: Form) -> String {
+    let mut text = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Syllable(syllable) => match form {
+                Form::Nfc => text.push(syllable.to_char()),
+                Form::Nfd => text.extend(syllable.jamo()),
+                Form::CompatibilityJamo => text.extend(syllable.compatibility_jamo()),
+            },
+            Segment::Space => text.push(' '),
+        }
+    }
+    text
+}
+
+#[derive(Clone)]
 pub struct Builder {
-    buf: String,
+    segments: Vec<Segment>,
     pos: Position,
+    initial_cons: Option<Consonant>,
     buffered_cons: Option<Consonant>,
 }
 
 impl Default for Builder {
     fn default() -> Self {
         Self {
-            buf: String::new(),
+            segments: Vec::new(),
             pos: Position::InitialConsonant,
+            initial_cons: None,
             buffered_cons: None,
         }
     }
@@ -121,17 +463,38 @@ impl Builder {
     }
 
     pub fn is_start_of_word(&self) -> bool {
-        (self.buf.is_empty() || self.buf.ends_with(' ')) && self.pos == Position::InitialConsonant
+        matches!(self.segments.last(), None | Some(Segment::Space))
+            && self.pos == Position::InitialConsonant
+    }
+
+    pub fn state(&self) -> State {
+        State {
+            position: self.pos,
+            buffered_consonant: self.buffered_cons,
+        }
     }
 
+    /// Fills up the current syllable until the position is reached. A
+    /// consonant that is still waiting to be placed closes the current
+    /// syllable if the position is the initial consonant of the next syllable
+    /// and otherwise starts the next syllable.
     pub fn advance_to(&mut self, pos: Position) {
+        if let Some(earlier_cons) = self.buffered_cons.take() {
+            if pos == Position::InitialConsonant {
+                self.pos = Position::FinalConsonant;
+                self.place_final(earlier_cons);
+            } else {
+                self.place_initial(earlier_cons);
+            }
+        }
+
         while self.pos != pos {
             match self.pos {
                 Position::InitialConsonant => {
-                    self.buf.push('ᄋ');
+                    self.initial_cons = Some(Consonant::Ng);
                 }
                 Position::Vowel => {
-                    self.buf.push('ᅳ');
+                    self.place_vowel(Vowel::Eu);
                 }
                 _ => {}
             };
@@ -139,123 +502,148 @@ impl Builder {
         }
     }
 
+    /// Pushes a consonant. Whether it ends up as a final consonant or as the
+    /// initial consonant of the next syllable is decided by what follows. A
+    /// consonant that can't be a final consonant starts a new syllable
+    /// instead.
     pub fn push_consonant(&mut self, cons: Consonant) {
         if let Some(earlier_cons) = self.buffered_cons.take() {
-            self.pos = match (earlier_cons, cons) {
-                // Avoid some combinations
-                (Consonant::P, Consonant::L) => Position::InitialConsonant,
-                _ => Position::FinalConsonant,
-            };
-            self.push_consonant(earlier_cons);
-        }
-
-        if self.pos == Position::Vowel {
-            self.advance_to(Position::SomeConsonant);
+            self.pos = Position::FinalConsonant;
+            self.place_final(earlier_cons);
         }
 
-        let c = match self.pos {
-            Position::InitialConsonant => match cons {
-                Consonant::B => 'ᄇ',
-                Consonant::J => 'ᄌ',
-                Consonant::D => 'ᄃ',
-                Consonant::G => 'ᄀ',
-                Consonant::S => 'ᄉ',
-                Consonant::M => 'ᄆ',
-                Consonant::N => 'ᄂ',
-                Consonant::Ng => 'ᄋ',
-                Consonant::L => 'ᄅ',
-                Consonant::H => 'ᄒ',
-                Consonant::K => 'ᄏ',
-                Consonant::T => 'ᄐ',
-                Consonant::Ch => 'ᄎ',
-                Consonant::P => 'ᄑ',
-                Consonant::Bb => 'ᄈ',
-                Consonant::Gg => 'ᄁ',
-                Consonant::Dd => 'ᄄ',
-            },
-            Position::Vowel => unreachable!(),
-            Position::FinalConsonant => match cons {
-                Consonant::B => 'ᆸ',
-                Consonant::J => 'ᆽ',
-                Consonant::D => 'ᆮ',
-                Consonant::G => 'ᆨ',
-                Consonant::S => 'ᆺ',
-                Consonant::M => 'ᆷ',
-                Consonant::N => 'ᆫ',
-                Consonant::Ng => 'ᆼ',
-                Consonant::L => 'ᆯ',
-                Consonant::H => 'ᇂ',
-                Consonant::K => 'ᆿ',
-                Consonant::T => 'ᇀ',
-                Consonant::Ch => 'ᆾ',
-                Consonant::P => 'ᇁ',
-                Consonant::Bb => panic!("ㅃ can't be in final consonant position"),
-                Consonant::Gg => panic!("ㄲ can't be in final consonant position"),
-                Consonant::Dd => panic!("ㄸ can't be in final consonant position"),
-            },
-            Position::SomeConsonant => {
+        match self.pos {
+            Position::InitialConsonant => self.place_initial(cons),
+            Position::FinalConsonant => self.place_final(cons),
+            Position::Vowel | Position::SomeConsonant => {
+                self.advance_to(Position::SomeConsonant);
                 self.buffered_cons = Some(cons);
                 self.pos = Position::InitialConsonant;
-                return;
             }
-        };
-        self.buf.push(c);
-        self.pos.advance();
+        }
     }
 
-    fn finish_syllable(&mut self) {
-        if let Some(earlier_cons) = self.buffered_cons.take() {
-            self.pos = Position::FinalConsonant;
-            self.push_consonant(earlier_cons);
+    /// Closes the current syllable with the final consonant.
+    pub fn push_final_consonant(&mut self, cons: Consonant) -> Result<(), Error> {
+        if !cons.can_be_final() {
+            return Err(Error::InvalidFinalConsonant(cons));
         }
+        self.advance_to(Position::FinalConsonant);
+        self.place_final(cons);
+        Ok(())
+    }
 
+    /// Closes the current syllable with the compound final consonant made up
+    /// of the two consonants, such as ㄺ for ㄹ and ㄱ.
+    pub fn push_final_cluster(&mut self, first: Consonant, second: Consonant) -> Result<(), Error> {
+        let final_consonant = FinalConsonant::Cluster(first, second);
+        if final_index(final_consonant).is_none() {
+            return Err(Error::InvalidFinalCluster(first, second));
+        }
+        self.advance_to(Position::FinalConsonant);
+        self.set_final(final_consonant);
+        Ok(())
+    }
+
+    fn place_initial(&mut self, cons: Consonant) {
+        self.advance_to(Position::InitialConsonant);
+        self.initial_cons = Some(cons);
+        self.pos = Position::Vowel;
+    }
+
+    /// Places the consonant as the final consonant of the current syllable.
+    /// If the syllable isn't complete yet, it gets filled up first. If the
+    /// consonant can't be a final consonant, it starts the next syllable
+    /// instead.
+    fn place_final(&mut self, cons: Consonant) {
+        if !cons.can_be_final() {
+            return self.place_initial(cons);
+        }
+        self.advance_to(Position::FinalConsonant);
+        self.set_final(FinalConsonant::Single(cons));
+    }
+
+    fn set_final(&mut self, final_consonant: FinalConsonant) {
+        if let Some(Segment::Syllable(syllable)) = self.segments.last_mut() {
+            syllable.final_consonant = Some(final_consonant);
+        }
+        self.pos = Position::InitialConsonant;
+    }
+
+    fn place_vowel(&mut self, vowel: Vowel) {
+        self.segments.push(Segment::Syllable(Syllable {
+            initial_consonant: self.initial_cons.take().unwrap_or(Consonant::Ng),
+            vowel,
+            final_consonant: None,
+        }));
+    }
+
+    fn finish_syllable(&mut self) {
         self.advance_to(Position::InitialConsonant);
     }
 
     pub fn push_space(&mut self) {
         self.finish_syllable();
-        self.buf.push(' ');
+        self.segments.push(Segment::Space);
     }
 
     pub fn push_vowel(&mut self, vowel: Vowel) {
         if let Some(earlier_cons) = self.buffered_cons.take() {
-            self.pos = Position::InitialConsonant;
-            self.push_consonant(earlier_cons);
+            self.place_initial(earlier_cons);
         }
 
         self.advance_to(Position::Vowel);
-        self.buf.push(match vowel {
-            Vowel::Ae => 'ᅢ',
-            Vowel::E => 'ᅦ',
-            Vowel::O => 'ᅩ',
-            Vowel::Eo => 'ᅥ',
-            Vowel::A => 'ᅡ',
-            Vowel::I => 'ᅵ',
-            Vowel::U => 'ᅮ',
-            Vowel::Eu => 'ᅳ',
-            Vowel::Wi => 'ᅱ',
-            Vowel::Wae => 'ᅫ',
-            Vowel::Wa => 'ᅪ',
-            Vowel::Wo => 'ᅯ',
-            Vowel::We => 'ᅰ',
-            Vowel::Yae => 'ᅤ',
-            Vowel::Ya => 'ᅣ',
-            Vowel::Yeo => 'ᅧ',
-            Vowel::Ye => 'ᅨ',
-            Vowel::Yo => 'ᅭ',
-            Vowel::Yu => 'ᅲ',
-        });
+        self.place_vowel(vowel);
         self.pos.advance();
     }
 
+    /// Finishes the text and returns it as precomposed syllables.
     pub fn finish(&mut self) -> impl Iterator<Item = char> + '_ {
+        self.finish_segments().iter().map(|segment| match segment {
+            Segment::Syllable(syllable) => syllable.to_char(),
+            Segment::Space => ' ',
+        })
+    }
+
+    /// Finishes the text and returns the syllables and spaces it consists of.
+    pub fn finish_segments(&mut self) -> &[Segment] {
         self.finish_syllable();
-        self.buf.nfc()
+        &self.segments
     }
 
     pub fn restart(&mut self) {
-        self.buf.clear();
+        self.segments.clear();
+        self.initial_cons = None;
         self.buffered_cons = None;
         self.pos = Position::InitialConsonant;
     }
 }
+
+impl SyllabaryBuilder for Builder {
+    type Consonant = Consonant;
+    type Vowel = Vowel;
+
+    fn is_start_of_word(&self) -> bool {
+        Builder::is_start_of_word(self)
+    }
+
+    fn push_consonant(&mut self, cons: Consonant) {
+        Builder::push_consonant(self, cons)
+    }
+
+    fn push_vowel(&mut self, vowel: Vowel) {
+        Builder::push_vowel(self, vowel)
+    }
+
+    fn push_space(&mut self) {
+        Builder::push_space(self)
+    }
+
+    fn finish_text(&mut self) -> String {
+        self.finish().collect()
+    }
+
+    fn restart(&mut self) {
+        Builder::restart(self)
+    }
+}
@@ -0,0 +1,420 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::hangul_builder::{Consonant, FinalConsonant, Syllable, Vowel};
+
+fn vowel_ipa(vowel: Vowel) -> &'static str {
+    match vowel {
+        Vowel::A => "a",
+        Vowel::Ae => "ɛ",
+        Vowel::Ya => "ja",
+        Vowel::Yae => "jɛ",
+        Vowel::Eo => "ʌ",
+        Vowel::E => "e",
+        Vowel::Yeo => "jʌ",
+        Vowel::Ye => "je",
+        Vowel::O => "o",
+        Vowel::Wa => "wa",
+        Vowel::Wae => "wɛ",
+        Vowel::Oe => "we",
+        Vowel::Yo => "jo",
+        Vowel::U => "u",
+        Vowel::Wo => "wʌ",
+        Vowel::We => "we",
+        Vowel::Wi => "wi",
+        Vowel::Yu => "ju",
+        Vowel::Eu => "ɯ",
+        Vowel::Ui => "ɰi",
+        Vowel::I => "i",
+    }
+}
+
+/// A syllable as it is pronounced after all the sound changes are applied.
+/// The final consonant is always one of the seven representative finals.
+#[derive(Copy, Clone)]
+pub(crate) struct Sound {
+    pub(crate) initial: Consonant,
+    pub(crate) vowel: Vowel,
+    pub(crate) final_cons: Option<Consonant>,
+}
+
+pub(crate) enum Token {
+    Word(Vec<Sound>),
+    Other(char),
+}
+
+/// A syllable as it is written, with its consonants being changed while the
+/// sound changes are applied.
+struct Spelling {
+    initial: Consonant,
+    vowel: Vowel,
+    finals: (Option<Consonant>, Option<Consonant>),
+}
+
+impl From<Syllable> for Spelling {
+    fn from(syllable: Syllable) -> Self {
+        Self {
+            initial: syllable.initial_consonant(),
+            vowel: syllable.vowel(),
+            finals: match syllable.final_consonant() {
+                None => (None, None),
+                Some(FinalConsonant::Single(cons)) => (Some(cons), None),
+                Some(FinalConsonant::Cluster(first, second)) => (Some(first), Some(second)),
+            },
+        }
+    }
+}
+
+fn tensed(cons: Consonant) -> Consonant {
+    match cons {
+        Consonant::G => Consonant::Gg,
+        Consonant::D => Consonant::Dd,
+        Consonant::B => Consonant::Bb,
+        Consonant::S => Consonant::Ss,
+        Consonant::J => Consonant::Jj,
+        c => c,
+    }
+}
+
+fn aspirated(cons: Consonant) -> Option<Consonant> {
+    Some(match cons {
+        Consonant::G | Consonant::Gg | Consonant::K => Consonant::K,
+        Consonant::D | Consonant::S | Consonant::Ss | Consonant::T => Consonant::T,
+        Consonant::J | Consonant::Ch => Consonant::Ch,
+        Consonant::B | Consonant::P => Consonant::P,
+        _ => return None,
+    })
+}
+
+/// Reduces a final consonant to one of the seven representative finals
+/// ㄱ, ㄴ, ㄷ, ㄹ, ㅁ, ㅂ and ㅇ.
+fn neutralized(cons: Consonant) -> Consonant {
+    match cons {
+        Consonant::G | Consonant::Gg | Consonant::K => Consonant::G,
+        Consonant::D
+        | Consonant::Dd
+        | Consonant::S
+        | Consonant::Ss
+        | Consonant::J
+        | Consonant::Jj
+        | Consonant::Ch
+        | Consonant::T => Consonant::D,
+        Consonant::H => Consonant::D,
+        Consonant::B | Consonant::Bb | Consonant::P => Consonant::B,
+        c => c,
+    }
+}
+
+/// Picks the consonant of a compound final that is actually pronounced.
+fn simplified(finals: (Option<Consonant>, Option<Consonant>)) -> Option<Consonant> {
+    match finals {
+        (Some(Consonant::L), Some(c @ Consonant::G))
+        | (Some(Consonant::L), Some(c @ Consonant::M))
+        | (Some(Consonant::L), Some(c @ Consonant::P)) => Some(c),
+        (first, _) => first,
+    }
+}
+
+fn is_obstruent(cons: Consonant) -> bool {
+    matches!(cons, Consonant::G | Consonant::D | Consonant::B)
+}
+
+/// Applies the sound changes of the standard pronunciation at the boundary
+/// between the final consonants of one syllable and the initial consonant of
+/// the next one. Returns the pronounced final consonant. Tensing can be left
+/// out, as some romanization systems don't reflect it.
+fn link(
+    finals: (Option<Consonant>, Option<Consonant>),
+    next: Option<&mut Spelling>,
+    tensing: bool,
+) -> Option<Consonant> {
+    let next = match next {
+        Some(next) => next,
+        None => return simplified(finals).map(neutralized),
+    };
+
+    let mut finals = finals;
+
+    if next.initial == Consonant::Ng {
+        // 연음: The final consonant moves over to the empty initial position.
+        // ㅎ is silent in front of a vowel.
+        let moving = match finals {
+            (Some(first), Some(Consonant::H)) => {
+                finals = (None, None);
+                Some(first)
+            }
+            (first, Some(second)) => {
+                finals = (first, None);
+                Some(if tensing && second == Consonant::S {
+                    Consonant::Ss
+                } else {
+                    second
+                })
+            }
+            (Some(Consonant::Ng), None) => None,
+            (Some(Consonant::H), None) => {
+                finals = (None, None);
+                None
+            }
+            (Some(first), None) => {
+                finals = (None, None);
+                Some(first)
+            }
+            (None, None) => None,
+        };
+
+        if let Some(moving) = moving {
+            // 구개음화: ㄷ and ㅌ turn into ㅈ and ㅊ in front of 이.
+            next.initial = match (moving, next.vowel) {
+                (Consonant::D, Vowel::I) => Consonant::J,
+                (Consonant::T, Vowel::I) => Consonant::Ch,
+                (c, _) => c,
+            };
+        }
+    } else if next.initial == Consonant::H {
+        // Aspiration of a preceding obstruent by ㅎ.
+        let (rest, last) = match finals {
+            (first, Some(second)) => (first, Some(second)),
+            (Some(first), None) => (None, Some(first)),
+            (None, None) => (None, None),
+        };
+        if let Some(aspirated) = last.and_then(aspirated) {
+            next.initial = aspirated;
+            finals = (rest, None);
+        }
+    } else if let Some(rest) = match finals {
+        (Some(Consonant::H), None) => Some(None),
+        (first, Some(Consonant::H)) => Some(first),
+        _ => None,
+    } {
+        // A final ㅎ aspirates the following consonant, turns ㅅ into ㅆ and
+        // assimilates to a following ㄴ.
+        match next.initial {
+            Consonant::S => {
+                if tensing {
+                    next.initial = Consonant::Ss;
+                }
+                finals = (rest, None);
+            }
+            Consonant::N => finals = (Some(rest.unwrap_or(Consonant::N)), None),
+            initial => {
+                if let Some(aspirated) = aspirated(initial) {
+                    next.initial = aspirated;
+                }
+                finals = (rest, None);
+            }
+        }
+    }
+
+    let mut final_cons = simplified(finals).map(neutralized);
+
+    if let Some(cons) = final_cons {
+        match (cons, next.initial) {
+            // ㄹ turns into ㄴ after ㅁ and ㅇ and, with the final consonant
+            // nasalized as well, after ㄱ and ㅂ.
+            (Consonant::M, Consonant::L) | (Consonant::Ng, Consonant::L) => {
+                next.initial = Consonant::N
+            }
+            (Consonant::G, Consonant::L) | (Consonant::B, Consonant::L) => {
+                next.initial = Consonant::N
+            }
+            // 유음화: ㄴ next to ㄹ turns into ㄹ.
+            (Consonant::N, Consonant::L) => final_cons = Some(Consonant::L),
+            (Consonant::L, Consonant::N) => next.initial = Consonant::L,
+            _ => {}
+        }
+    }
+
+    if let Some(cons) = final_cons {
+        if is_obstruent(cons) {
+            match next.initial {
+                // 비음화: Obstruents turn into nasals in front of nasals.
+                Consonant::N | Consonant::M => {
+                    final_cons = Some(match cons {
+                        Consonant::G => Consonant::Ng,
+                        Consonant::D => Consonant::N,
+                        _ => Consonant::M,
+                    })
+                }
+                // 경음화: Lenis consonants turn tense after obstruents.
+                initial if tensing => next.initial = tensed(initial),
+                _ => {}
+            }
+        }
+    }
+
+    final_cons
+}
+
+fn pronounce_word(syllables: &mut [Spelling], tensing: bool) -> Vec<Sound> {
+    let mut sounds = Vec::with_capacity(syllables.len());
+    let mut rest = syllables;
+    while let Some((current, tail)) = std::mem::take(&mut rest).split_first_mut() {
+        let final_cons = link(current.finals, tail.first_mut(), tensing);
+        sounds.push(Sound {
+            initial: current.initial,
+            vowel: current.vowel,
+            final_cons,
+        });
+        rest = tail;
+    }
+    sounds
+}
+
+/// Splits the text into words of 한글 syllables with all the sound changes
+/// applied and the other characters in between.
+pub(crate) fn pronounce(hangul: &str, tensing: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = Vec::new();
+
+    for c in hangul.nfc() {
+        if let Some(syllable) = Syllable::from_char(c) {
+            word.push(Spelling::from(syllable));
+        } else {
+            if !word.is_empty() {
+                tokens.push(Token::Word(pronounce_word(&mut word, tensing)));
+                word.clear();
+            }
+            tokens.push(Token::Other(c));
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(pronounce_word(&mut word, tensing)));
+    }
+
+    tokens
+}
+
+fn push_initial(
+    ipa: &mut String,
+    cons: Consonant,
+    voiced_context: bool,
+    after_l: bool,
+    vowel: Vowel,
+) {
+    let palatal = vowel == Vowel::I || vowel_ipa(vowel).starts_with('j');
+    ipa.push_str(match cons {
+        Consonant::G if voiced_context => "ɡ",
+        Consonant::G => "k",
+        Consonant::Gg => "k͈",
+        Consonant::K => "kʰ",
+        Consonant::N => "n",
+        Consonant::D if voiced_context => "d",
+        Consonant::D => "t",
+        Consonant::Dd => "t͈",
+        Consonant::T => "tʰ",
+        Consonant::L if after_l => "l",
+        Consonant::L => "ɾ",
+        Consonant::M => "m",
+        Consonant::B if voiced_context => "b",
+        Consonant::B => "p",
+        Consonant::Bb => "p͈",
+        Consonant::P => "pʰ",
+        Consonant::S if palatal => "ɕ",
+        Consonant::S => "s",
+        Consonant::Ss if palatal => "ɕ͈",
+        Consonant::Ss => "s͈",
+        Consonant::Ng => "",
+        Consonant::J if voiced_context => "dʑ",
+        Consonant::J => "tɕ",
+        Consonant::Jj => "t͈ɕ",
+        Consonant::Ch => "tɕʰ",
+        Consonant::H => "h",
+    });
+}
+
+fn push_vowel(ipa: &mut String, initial: Consonant, vowel: Vowel) {
+    let ipa_vowel = vowel_ipa(vowel);
+    ipa.push_str(match (initial, vowel) {
+        // ㅢ is pronounced as ㅣ after a consonant.
+        (Consonant::Ng, Vowel::Ui) => ipa_vowel,
+        (_, Vowel::Ui) => "i",
+        // The glide is absorbed by the palatal consonants ㅈ, ㅉ and ㅊ.
+        (Consonant::J, _) | (Consonant::Jj, _) | (Consonant::Ch, _) => {
+            ipa_vowel.trim_start_matches('j')
+        }
+        _ => ipa_vowel,
+    });
+}
+
+fn push_final(ipa: &mut String, cons: Consonant) {
+    ipa.push_str(match cons {
+        Consonant::G => "k̚",
+        Consonant::D => "t̚",
+        Consonant::B => "p̚",
+        Consonant::N => "n",
+        Consonant::M => "m",
+        Consonant::Ng => "ŋ",
+        _ => "l",
+    });
+}
+
+/// Transcribes 한글 into IPA following the rules of the standard
+/// pronunciation (표준 발음법), i.e. the way a Korean reader would pronounce
+/// the text. Characters that aren't 한글 syllables are kept as is.
+pub fn convert(hangul: &str) -> String {
+    let mut ipa = String::new();
+
+    for token in pronounce(hangul, true) {
+        match token {
+            Token::Word(sounds) => {
+                let mut previous: Option<Sound> = None;
+                for sound in sounds {
+                    let voiced_context = match previous {
+                        Some(previous) => match previous.final_cons {
+                            None => true,
+                            Some(cons) => !is_obstruent(cons),
+                        },
+                        None => false,
+                    };
+                    let after_l = previous.and_then(|p| p.final_cons) == Some(Consonant::L);
+                    push_initial(
+                        &mut ipa,
+                        sound.initial,
+                        voiced_context,
+                        after_l,
+                        sound.vowel,
+                    );
+                    push_vowel(&mut ipa, sound.initial, sound.vowel);
+                    if let Some(cons) = sound.final_cons {
+                        push_final(&mut ipa, cons);
+                    }
+                    previous = Some(sound);
+                }
+            }
+            Token::Other(c) => ipa.push(c),
+        }
+    }
+
+    ipa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_the_sound_changes() {
+        let cases = [
+            // 연음
+            ("음악", "ɯmak̚"),
+            ("옷이", "oɕi"),
+            // 비음화
+            ("국물", "kuŋmul"),
+            // 유음화
+            ("신라", "ɕilla"),
+            // 경음화
+            ("먹다", "mʌk̚t͈a"),
+            ("학교", "hak̚k͈jo"),
+            // 구개음화
+            ("같이", "katɕʰi"),
+            ("해돋이", "hɛdodʑi"),
+            // 음절의 끝소리 규칙
+            ("부엌", "puʌk̚"),
+            ("꽃", "k͈ot̚"),
+            ("값", "kap̚"),
+        ];
+        for &(hangul, ipa) in &cases {
+            assert_eq!(convert(hangul), ipa, "{}", hangul);
+        }
+    }
+}
@@ -1,150 +1,725 @@
-use crate::hangul_builder::{self, Consonant, Position, Vowel};
+use std::{cmp::Ordering, fmt, iter};
 
-pub fn convert(
-    builder: &mut hangul_builder::Builder,
-    phonetics: impl IntoIterator<Item = char>,
-) -> impl Iterator<Item = char> + '_ {
-    builder.restart();
+use crate::{
+    accent::Accent,
+    fidelity,
+    hangul_builder::{self, Consonant, Position, State, Vowel},
+    syllabary::{self, is_vowel},
+    syllabification::{self, Role},
+};
 
-    let mut chars = phonetics.into_iter().peekable();
+/// The maximum amount of partial transcriptions that are followed when
+/// looking for candidates.
+const MAX_BRANCHES: usize = 64;
 
-    while let Some(c) = chars.next() {
-        match c {
-            'n' => builder.push_consonant(Consonant::N),
-            'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => builder.push_vowel(Vowel::Eo),
-            'k' => builder.push_consonant(if builder.is_start_of_word() {
-                Consonant::G
-            } else {
-                Consonant::K
-            }),
-            't' => {
-                let cons = if chars.peek().copied() == Some('ʃ') {
-                    chars.next();
-                    Consonant::Ch
-                } else if builder.is_start_of_word() {
-                    Consonant::D
-                } else {
-                    Consonant::T
-                };
+#[derive(Copy, Clone)]
+enum Action {
+    Consonant(Consonant),
+    Vowel(Vowel),
+    Final(Consonant),
+    AdvanceTo(Position),
+    Space,
+}
 
-                builder.push_consonant(cons)
-            }
-            'p' => builder.push_consonant(if builder.is_start_of_word() {
-                Consonant::B
-            } else {
-                Consonant::P
-            }),
-            'b' => builder.push_consonant(if builder.is_start_of_word() {
-                Consonant::Bb
-            } else {
-                Consonant::B
-            }),
-            'g' | 'ɡ' => builder.push_consonant(if builder.is_start_of_word() {
-                Consonant::Gg
-            } else {
-                Consonant::G
-            }),
-            'd' => builder.push_consonant(if chars.peek().copied() == Some('ʒ') {
-                chars.next();
-                Consonant::J
-            } else if builder.is_start_of_word() {
-                Consonant::Dd
+impl Action {
+    fn jamo(self) -> Option<char> {
+        match self {
+            Action::Consonant(cons) | Action::Final(cons) => Some(cons.compatibility_jamo()),
+            Action::Vowel(vowel) => Some(vowel.compatibility_jamo()),
+            Action::AdvanceTo(_) | Action::Space => None,
+        }
+    }
+}
+
+/// One way of transcribing the symbol at the current position. It may
+/// consume additional symbols that follow.
+struct Alternative {
+    skip: usize,
+    actions: Vec<Action>,
+}
+
+impl Alternative {
+    fn new(actions: Vec<Action>) -> Self {
+        Self { skip: 0, actions }
+    }
+
+    fn skipping(skip: usize, actions: Vec<Action>) -> Self {
+        Self { skip, actions }
+    }
+
+    /// Makes the consonant the alternative ends with close the syllable,
+    /// either as a final consonant or followed by an epenthetic ㅡ.
+    fn closing(mut self, as_final: bool) -> Self {
+        if let Some(&Action::Consonant(cons)) = self.actions.last() {
+            if as_final {
+                // Only ㅂ, ㅅ and ㄱ are used for the voiceless stops.
+                let cons = match cons {
+                    Consonant::P => Consonant::B,
+                    Consonant::T => Consonant::S,
+                    Consonant::K => Consonant::G,
+                    cons => cons,
+                };
+                self.actions.pop();
+                self.actions.push(Action::Final(cons));
             } else {
-                Consonant::D
-            }),
-            'ð' | 'θ' => builder.push_consonant(Consonant::D),
-            'l' => builder.push_consonant(Consonant::L),
-            'r' | 'ɹ' => {
-                if chars.peek().is_some() {
-                    builder.push_consonant(Consonant::L)
-                }
+                return self.followed_by(Vowel::Eu);
             }
-            'ə' | 'ɜ' | 'ɝ' => builder.push_vowel(Vowel::Eu),
-            'a' | 'ɐ' => builder.push_vowel(Vowel::A),
-            'ʊ' | 'u' => builder.push_vowel(Vowel::U),
-            's' | 'z' => {
-                builder.advance_to(Position::InitialConsonant);
-                builder.push_consonant(Consonant::S)
-            }
-            'h' => builder.push_consonant(Consonant::H),
-            'm' => builder.push_consonant(Consonant::M),
-            'j' | 'ɪ' | 'y' | 'i' => {
-                let mut vowel = Vowel::I;
-                if let Some(c) = chars.peek() {
-                    if let Some(v) = match c {
-                        'ɛ' | 'æ' => Some(Vowel::Yae),
-                        'a' | 'ɐ' => Some(Vowel::Ya),
-                        'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => Some(Vowel::Yeo),
-                        'e' => Some(Vowel::Ye),
-                        'o' => Some(Vowel::Yo),
-                        'ʊ' | 'u' => Some(Vowel::Yu),
-                        _ => None,
-                    } {
-                        chars.next();
-                        vowel = v;
-                    }
-                }
-                builder.push_vowel(vowel)
+        }
+        self
+    }
+
+    /// Adds an epenthetic vowel if the alternative ends with a consonant.
+    fn followed_by(mut self, vowel: Vowel) -> Self {
+        if let Some(&Action::Consonant(_)) = self.actions.last() {
+            self.actions.push(Action::Vowel(vowel));
+        }
+        self
+    }
+}
+
+/// How pronunciations are transcribed into 한글.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// The accent of English the transcription follows. With the British
+    /// accent, an r before a consonant is dropped and ə is written as 어 like
+    /// in most loanwords.
+    pub accent: Accent,
+}
+
+/// The IPA symbols that were transcribed together, along with the jamo they
+/// were turned into. The jamo that the builder adds on its own, such as the
+/// ㅡ that separates consonants, aren't part of any step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    pub ipa: String,
+    pub jamo: String,
+    /// The name of the rule that decided the transcription.
+    pub rule: &'static str,
+    /// Whether the symbols start a word, which some rules depend on.
+    pub word_start: bool,
+    /// The symbol following the first one, which rules may look ahead at.
+    pub lookahead: Option<char>,
+    /// The part of the syllable the first symbol belongs to.
+    pub role: Role,
+    pub before: State,
+    pub after: State,
+}
+
+impl Step {
+    /// Describes what the rule took into account, such as the part of the
+    /// syllable and the symbol that follows.
+    pub fn context(&self) -> String {
+        let mut context = self.role.to_string();
+        if self.word_start {
+            context.push_str(", word start");
+        }
+        if let Some(lookahead) = self.lookahead {
+            context.push_str(", before ");
+            context.push(lookahead);
+        }
+        context
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} → {}: {} ({}), {} → {}",
+            self.ipa,
+            self.jamo,
+            self.rule,
+            self.context(),
+            self.before,
+            self.after
+        )
+    }
+}
+
+/// A possible transcription of a pronunciation into 한글.
+pub struct Candidate {
+    pub hangul: String,
+    /// How faithfully the 한글 reproduces the pronunciation, from 0 to 1.
+    pub score: f32,
+    /// The alternative that was chosen for each rule that was applied.
+    choices: Vec<usize>,
+}
+
+fn y_vowel(c: Option<char>) -> Option<Vowel> {
+    Some(match c? {
+        'ɛ' | 'æ' => Vowel::Yae,
+        'a' | 'ɐ' => Vowel::Ya,
+        'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => Vowel::Yeo,
+        'e' => Vowel::Ye,
+        'o' => Vowel::Yo,
+        'ʊ' | 'u' => Vowel::Yu,
+        _ => return None,
+    })
+}
+
+fn w_vowel(c: Option<char>) -> Option<Vowel> {
+    Some(match c? {
+        'j' | 'ɪ' | 'y' | 'i' => Vowel::Wi,
+        'ɛ' | 'æ' => Vowel::Wae,
+        'a' | 'ɐ' => Vowel::Wa,
+        'o' | 'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => Vowel::Wo,
+        'e' => Vowel::We,
+        _ => return None,
+    })
+}
+
+/// A rule that decides how a symbol is transcribed, along with the ways it
+/// can be transcribed, the preferred one first.
+struct Rule {
+    name: &'static str,
+    alternatives: Vec<Alternative>,
+}
+
+impl Rule {
+    fn new(name: &'static str, alternatives: Vec<Alternative>) -> Self {
+        Self { name, alternatives }
+    }
+
+    fn map(self, f: impl FnMut(Alternative) -> Alternative) -> Self {
+        Self {
+            name: self.name,
+            alternatives: self.alternatives.into_iter().map(f).collect(),
+        }
+    }
+}
+
+/// Finds the rule for the symbol. It has no alternatives if the symbol is
+/// unknown.
+fn rule(builder: &hangul_builder::Builder, c: char, next: Option<char>, options: Options) -> Rule {
+    use Action::{AdvanceTo, Consonant as C, Final, Vowel as V};
+
+    let start = builder.is_start_of_word();
+    let british = options.accent == Accent::British;
+
+    match c {
+        'n' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::N)])]),
+        'ʌ' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::Eo)])]),
+        'ɔ' | 'ɒ' if british => Rule::new(
+            "british open o",
+            vec![
+                Alternative::new(vec![V(Vowel::O)]),
+                Alternative::new(vec![V(Vowel::Eo)]),
+            ],
+        ),
+        'ɔ' | 'ɒ' => Rule::new(
+            "open o",
+            vec![
+                Alternative::new(vec![V(Vowel::Eo)]),
+                Alternative::new(vec![V(Vowel::O)]),
+            ],
+        ),
+        'ɑ' if british => Rule::new(
+            "british open a",
+            vec![
+                Alternative::new(vec![V(Vowel::A)]),
+                Alternative::new(vec![V(Vowel::Eo)]),
+            ],
+        ),
+        'ɑ' => Rule::new(
+            "open a",
+            vec![
+                Alternative::new(vec![V(Vowel::Eo)]),
+                Alternative::new(vec![V(Vowel::A)]),
+            ],
+        ),
+        'k' if start => Rule::new(
+            "voiceless stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::G)]),
+                Alternative::new(vec![C(Consonant::K)]),
+            ],
+        ),
+        'k' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::K)])]),
+        't' if next == Some('ʃ') => Rule::new(
+            "affricate",
+            vec![Alternative::skipping(1, vec![C(Consonant::Ch)])],
+        ),
+        't' if start => Rule::new(
+            "voiceless stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::D)]),
+                Alternative::new(vec![C(Consonant::T)]),
+            ],
+        ),
+        't' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::T)])]),
+        'p' if start => Rule::new(
+            "voiceless stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::B)]),
+                Alternative::new(vec![C(Consonant::P)]),
+            ],
+        ),
+        'p' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::P)])]),
+        'b' if start => Rule::new(
+            "voiced stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::Bb)]),
+                Alternative::new(vec![C(Consonant::B)]),
+            ],
+        ),
+        'b' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::B)])]),
+        'g' | 'ɡ' if start => Rule::new(
+            "voiced stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::Gg)]),
+                Alternative::new(vec![C(Consonant::G)]),
+            ],
+        ),
+        'g' | 'ɡ' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::G)])]),
+        'd' if next == Some('ʒ') => Rule::new(
+            "affricate",
+            vec![Alternative::skipping(1, vec![C(Consonant::J)])],
+        ),
+        'd' if start => Rule::new(
+            "voiced stop at word start",
+            vec![
+                Alternative::new(vec![C(Consonant::Dd)]),
+                Alternative::new(vec![C(Consonant::D)]),
+            ],
+        ),
+        'd' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::D)])]),
+        'ð' => Rule::new("th", vec![Alternative::new(vec![C(Consonant::D)])]),
+        'θ' => Rule::new(
+            "th",
+            vec![
+                Alternative::new(vec![C(Consonant::D)]),
+                Alternative::new(vec![C(Consonant::S)]),
+            ],
+        ),
+        'l' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::L)])]),
+        'r' | 'ɹ' if british && !matches!(next, Some(next) if is_vowel(next)) => Rule::new(
+            "british r without vowel",
+            vec![
+                Alternative::new(vec![]),
+                Alternative::new(vec![C(Consonant::L), V(Vowel::Eu)]),
+            ],
+        ),
+        'r' | 'ɹ' if next.is_some() => {
+            Rule::new("r", vec![Alternative::new(vec![C(Consonant::L)])])
+        }
+        'r' | 'ɹ' => Rule::new(
+            "r at word end",
+            vec![
+                Alternative::new(vec![]),
+                Alternative::new(vec![C(Consonant::L), V(Vowel::Eu)]),
+            ],
+        ),
+        // The British GOAT vowel əʊ, which American English has as oʊ.
+        'ə' if british && next == Some('ʊ') => Rule::new(
+            "goat vowel",
+            vec![Alternative::skipping(1, vec![V(Vowel::O)])],
+        ),
+        'ə' | 'ɜ' | 'ɝ' if british => Rule::new(
+            "british schwa",
+            vec![
+                Alternative::new(vec![V(Vowel::Eo)]),
+                Alternative::new(vec![V(Vowel::Eu)]),
+            ],
+        ),
+        'ə' | 'ɜ' | 'ɝ' => Rule::new(
+            "schwa",
+            vec![
+                Alternative::new(vec![V(Vowel::Eu)]),
+                Alternative::new(vec![V(Vowel::Eo)]),
+            ],
+        ),
+        'a' | 'ɐ' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::A)])]),
+        'ʊ' | 'u' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::U)])]),
+        's' | 'z' => Rule::new(
+            "sibilant",
+            vec![
+                Alternative::new(vec![AdvanceTo(Position::InitialConsonant), C(Consonant::S)]),
+                Alternative::new(vec![C(Consonant::S)]),
+            ],
+        ),
+        'h' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::H)])]),
+        'm' => Rule::new("consonant", vec![Alternative::new(vec![C(Consonant::M)])]),
+        'j' | 'ɪ' | 'y' | 'i' => match y_vowel(next) {
+            Some(vowel) => Rule::new(
+                "y diphthong",
+                vec![Alternative::skipping(1, vec![V(vowel)])],
+            ),
+            None => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::I)])]),
+        },
+        'f' => Rule::new("f", vec![Alternative::new(vec![C(Consonant::P)])]),
+        'o' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::O)])]),
+        'ŋ' => Rule::new("ng", vec![Alternative::new(vec![Final(Consonant::Ng)])]),
+        'ʃ' | 'ʒ' => {
+            let vowel = match next {
+                Some('j') | Some('ɪ') | Some('y') | Some('i') => Some(Vowel::I),
+                _ => y_vowel(next),
+            };
+            match vowel {
+                Some(vowel) => Rule::new(
+                    "sh before vowel",
+                    vec![Alternative::skipping(1, vec![C(Consonant::S), V(vowel)])],
+                ),
+                None => Rule::new(
+                    "sh",
+                    vec![Alternative::new(vec![C(Consonant::S), V(Vowel::I)])],
+                ),
             }
-            'f' => builder.push_consonant(Consonant::P),
-            'o' => builder.push_vowel(Vowel::O),
-            'ŋ' => {
-                builder.advance_to(Position::FinalConsonant);
-                builder.push_consonant(Consonant::Ng)
+        }
+        'w' | 'v' if next.is_some() => {
+            let mut rule = match w_vowel(next) {
+                Some(vowel) => Rule::new(
+                    "w diphthong",
+                    vec![Alternative::skipping(1, vec![V(vowel)])],
+                ),
+                None => Rule::new("w", vec![Alternative::new(vec![V(Vowel::U)])]),
+            };
+            if c == 'v' {
+                rule.alternatives
+                    .push(Alternative::new(vec![C(Consonant::B)]));
             }
-            'ʃ' | 'ʒ' => {
-                builder.push_consonant(Consonant::S);
-                let mut vowel = Vowel::I;
-                if let Some(c) = chars.peek() {
-                    if let Some(v) = match c {
-                        'ɛ' | 'æ' => Some(Vowel::Yae),
-                        'a' | 'ɐ' => Some(Vowel::Ya),
-                        'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => Some(Vowel::Yeo),
-                        'e' => Some(Vowel::Ye),
-                        'o' => Some(Vowel::Yo),
-                        'ʊ' | 'u' => Some(Vowel::Yu),
-                        'j' | 'ɪ' | 'y' | 'i' => Some(Vowel::I),
-                        _ => None,
-                    } {
-                        chars.next();
-                        vowel = v;
-                    }
+            rule
+        }
+        'w' | 'v' => Rule::new(
+            "w at word end",
+            vec![
+                Alternative::new(vec![C(Consonant::B)]),
+                Alternative::new(vec![C(Consonant::B), V(Vowel::Eu)]),
+            ],
+        ),
+        'e' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::E)])]),
+        'ɛ' => Rule::new(
+            "open e",
+            vec![
+                Alternative::new(vec![V(Vowel::Ae)]),
+                Alternative::new(vec![V(Vowel::E)]),
+            ],
+        ),
+        'æ' => Rule::new("vowel", vec![Alternative::new(vec![V(Vowel::Ae)])]),
+        'ʦ' => Rule::new(
+            "ts",
+            vec![
+                Alternative::new(vec![Final(Consonant::T), C(Consonant::S)]),
+                Alternative::new(vec![C(Consonant::Ch)]),
+                Alternative::new(vec![C(Consonant::Jj)]),
+            ],
+        ),
+        'ˈ' | 'ː' | '\'' | 'ˌ' | '.' => Rule::new("ignored", vec![Alternative::new(vec![])]),
+        '|' => Rule::new("word boundary", vec![Alternative::new(vec![Action::Space])]),
+        _ => Rule::new("unknown symbol", vec![]),
+    }
+}
+
+/// Finds the rule for the symbol at the index, taking its place in the
+/// syllable into account like the loanword orthography does.
+fn rule_at(
+    builder: &hangul_builder::Builder,
+    chars: &[char],
+    roles: &[Role],
+    index: usize,
+    options: Options,
+) -> Rule {
+    use Action::{Consonant as C, Final};
+
+    let c = chars[index];
+    let rule = rule(builder, c, chars.get(index + 1).copied(), options);
+    let is_sound = |&i: &usize| roles[i] != Role::Other;
+    let next = (index + 1..chars.len()).find(is_sound).map(|i| chars[i]);
+    let previous = (0..index).rev().find(is_sound).map(|i| roles[i]);
+
+    match roles[index] {
+        Role::Coda { first, short_vowel } => {
+            let as_final = match (c, chars.get(index + 1)) {
+                // The affricates are followed by ㅣ instead, like in 샌드위치.
+                ('t', Some('ʃ')) | ('d', Some('ʒ')) => {
+                    return Rule::new("affricate in coda", rule.alternatives)
+                        .map(|a| a.followed_by(Vowel::I));
+                }
+                // A voiceless stop after a short vowel is a final consonant,
+                // unless a liquid or a nasal follows.
+                ('p', _) | ('t', _) | ('k', _) => {
+                    first
+                        && short_vowel
+                        && !matches!(
+                            next,
+                            Some('l') | Some('ɹ') | Some('r') | Some('m') | Some('n')
+                        )
                 }
-                builder.push_vowel(vowel);
+                ('m', _) | ('n', _) | ('ŋ', _) | ('l', _) => first,
+                ('ɹ', _) | ('r', _) => return rule,
+                _ => false,
+            };
+            let alternatives = match c {
+                'v' => vec![Alternative::new(vec![C(Consonant::B)])],
+                _ => rule.alternatives,
+            };
+            let name = if as_final {
+                "final consonant in coda"
+            } else {
+                "epenthetic ㅡ in coda"
+            };
+            Rule::new(name, alternatives).map(|a| a.closing(as_final))
+        }
+        // An l between vowels is written as ㄹㄹ.
+        Role::Onset
+            if c == 'l'
+                && !builder.is_start_of_word()
+                && matches!(previous, Some(Role::Nucleus) | Some(Role::Onset)) =>
+        {
+            Rule::new(
+                "l between vowels",
+                vec![Alternative::new(vec![Final(Consonant::L), C(Consonant::L)])],
+            )
+        }
+        // A consonant that is followed by more of the onset is written with
+        // ㅡ, like in 스트리트, instead of closing the previous syllable.
+        Role::Onset => {
+            let in_cluster = |alternative: &Alternative| {
+                let end = index + 1 + alternative.skip;
+                matches!((end..chars.len()).find(is_sound), Some(i) if roles[i] == Role::Onset)
+            };
+            if !rule.alternatives.iter().any(in_cluster) {
+                return rule;
             }
-            'w' | 'v' => {
-                if let Some(c) = chars.peek() {
-                    if let Some(v) = match c {
-                        'j' | 'ɪ' | 'y' | 'i' => Some(Vowel::Wi),
-                        'ɛ' | 'æ' => Some(Vowel::Wae),
-                        'a' | 'ɐ' => Some(Vowel::Wa),
-                        'o' | 'ʌ' | 'ɔ' | 'ɒ' | 'ɑ' => Some(Vowel::Wo),
-                        'e' => Some(Vowel::We),
-                        _ => None,
-                    } {
-                        chars.next();
-                        builder.push_vowel(v);
-                    } else {
-                        builder.push_vowel(Vowel::U);
-                    }
+            Rule::new("consonant cluster in onset", rule.alternatives).map(|a| {
+                if in_cluster(&a) {
+                    a.followed_by(Vowel::Eu)
                 } else {
-                    builder.push_consonant(Consonant::B);
+                    a
                 }
+            })
+        }
+        _ => rule,
+    }
+}
+
+fn apply(builder: &mut hangul_builder::Builder, actions: &[Action]) {
+    for &action in actions {
+        match action {
+            Action::Consonant(cons) => builder.push_consonant(cons),
+            Action::Vowel(vowel) => builder.push_vowel(vowel),
+            Action::Final(cons) => {
+                if builder.push_final_consonant(cons).is_err() {
+                    builder.push_consonant(cons);
+                }
+            }
+            Action::AdvanceTo(pos) => builder.advance_to(pos),
+            Action::Space => builder.push_space(),
+        }
+    }
+}
+
+pub fn convert(
+    builder: &mut hangul_builder::Builder,
+    phonetics: impl IntoIterator<Item = char>,
+) -> impl Iterator<Item = char> + '_ {
+    convert_with(builder, phonetics, Options::default())
+}
+
+pub fn convert_with(
+    builder: &mut hangul_builder::Builder,
+    phonetics: impl IntoIterator<Item = char>,
+    options: Options,
+) -> impl Iterator<Item = char> + '_ {
+    transcribe(builder, phonetics, options, iter::repeat(0), |_| {});
+    builder.finish()
+}
+
+/// Transcribes the pronunciation like [`convert_with`], but also returns the
+/// steps that the 한글 was assembled in. The 한글 can be taken from the
+/// builder afterwards.
+pub fn trace(
+    builder: &mut hangul_builder::Builder,
+    phonetics: impl IntoIterator<Item = char>,
+    options: Options,
+) -> Vec<Step> {
+    let mut steps = Vec::new();
+    transcribe(builder, phonetics, options, iter::repeat(0), |step| {
+        steps.push(step)
+    });
+    steps
+}
+
+/// Returns the steps that the candidate was assembled in, like [`trace`]
+/// does for the preferred alternatives. The pronunciation and the options
+/// need to be the ones the candidate was found with.
+pub fn trace_candidate(
+    builder: &mut hangul_builder::Builder,
+    phonetics: impl IntoIterator<Item = char>,
+    options: Options,
+    candidate: &Candidate,
+) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let choices = candidate.choices.iter().copied();
+    transcribe(builder, phonetics, options, choices, |step| {
+        steps.push(step)
+    });
+    steps
+}
+
+/// Transcribes the pronunciation with the chosen alternative of each rule
+/// that applies, reporting each step.
+fn transcribe(
+    builder: &mut hangul_builder::Builder,
+    phonetics: impl IntoIterator<Item = char>,
+    options: Options,
+    mut choices: impl Iterator<Item = usize>,
+    mut on_step: impl FnMut(Step),
+) {
+    let mut roles = None;
+    syllabary::transcribe(builder, phonetics, |builder, chars, index| {
+        let roles = roles.get_or_insert_with(|| syllabification::syllabify(chars));
+        let rule = rule_at(builder, chars, roles, index, options);
+        if rule.alternatives.is_empty() {
+            return None;
+        }
+        let choice = choices.next().unwrap_or(0);
+        let alternative = rule.alternatives.into_iter().nth(choice)?;
+        let before = builder.state();
+        let word_start = builder.is_start_of_word();
+        apply(builder, &alternative.actions);
+        let end = (index + 1 + alternative.skip).min(chars.len());
+        on_step(Step {
+            ipa: chars[index..end].iter().collect(),
+            jamo: alternative
+                .actions
+                .iter()
+                .filter_map(|a| a.jamo())
+                .collect(),
+            rule: rule.name,
+            word_start,
+            lookahead: chars.get(index + 1).copied(),
+            role: roles[index],
+            before,
+            after: builder.state(),
+        });
+        Some(alternative.skip)
+    });
+}
+
+/// Lists the different ways the pronunciation can be transcribed into 한글,
+/// ranked by how faithfully they reproduce the pronunciation. At most `n`
+/// candidates are returned.
+pub fn candidates(phonetics: impl IntoIterator<Item = char>, n: usize) -> Vec<Candidate> {
+    candidates_with(phonetics, n, Options::default())
+}
+
+pub fn candidates_with(
+    phonetics: impl IntoIterator<Item = char>,
+    n: usize,
+    options: Options,
+) -> Vec<Candidate> {
+    let chars = phonetics.into_iter().collect::<Vec<_>>();
+    let roles = syllabification::syllabify(&chars);
+
+    // Each branch tracks how often it deviated from the preferred
+    // transcription, so the closest variants survive when there are too many.
+    let mut branches = vec![(hangul_builder::Builder::new(), 0, 0, Vec::new())];
+    let mut finished = Vec::new();
+
+    while !branches.is_empty() {
+        let mut next_branches = Vec::new();
+
+        for (builder, index, deviations, choices) in branches {
+            if index >= chars.len() {
+                finished.push((builder, choices));
+                continue;
             }
-            'e' => builder.push_vowel(Vowel::E),
-            'ɛ' | 'æ' => builder.push_vowel(Vowel::Ae),
-            'ʦ' => {
-                builder.advance_to(Position::FinalConsonant);
-                builder.push_consonant(Consonant::T);
-                builder.push_consonant(Consonant::S);
+
+            let alternatives = rule_at(&builder, &chars, &roles, index, options).alternatives;
+            if alternatives.is_empty() {
+                next_branches.push((builder, index + 1, deviations, choices));
+                continue;
             }
-            'ˈ' | 'ː' | '\'' | 'ˌ' => {} // Explicitly ignored
-            '|' => builder.push_space(),
-            c => {
-                println!("{} is unknown", c);
+
+            for (i, alternative) in alternatives.iter().enumerate() {
+                let mut builder = builder.clone();
+                apply(&mut builder, &alternative.actions);
+                let mut choices = choices.clone();
+                choices.push(i);
+                next_branches.push((
+                    builder,
+                    index + 1 + alternative.skip,
+                    deviations + (i != 0) as usize,
+                    choices,
+                ));
             }
         }
+
+        next_branches.sort_by_key(|&(_, _, deviations, _)| deviations);
+        next_branches.truncate(MAX_BRANCHES);
+        branches = next_branches;
     }
 
-    builder.finish()
+    let ipa = chars.iter().collect::<String>();
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for (mut builder, choices) in finished {
+        let hangul = builder.finish().collect::<String>();
+        if candidates.iter().any(|c| c.hangul == hangul) {
+            continue;
+        }
+        let score = fidelity::score(&ipa, &hangul);
+        candidates.push(Candidate {
+            hangul,
+            score,
+            choices,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    candidates.truncate(n);
+    candidates
+}
+
+/// Transcribes the pronunciation into the candidate that reproduces it most
+/// faithfully, instead of always following the preferred rules.
+pub fn convert_best(phonetics: impl IntoIterator<Item = char>, options: Options) -> String {
+    candidates_with(phonetics, 1, options)
+        .pop()
+        .map(|candidate| candidate.hangul)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_onset_clusters_with_epenthetic_eu() {
+        let cases = [
+            ("ʌbɹɔd", "어브러드"),
+            ("ˈsikɹət", "시크릇"),
+            // The glide j joins the vowel instead of the cluster.
+            ("kəmˈpjutəɹ", "금퓨트"),
+            ("ˈɛkstɹə", "액스트르"),
+            ("stɹit", "스트리트"),
+            ("ʌˈplaɪ", "어플라이"),
+        ];
+        for &(ipa, hangul) in &cases {
+            assert_eq!(
+                convert(&mut hangul_builder::Builder::new(), ipa.chars()).collect::<String>(),
+                hangul,
+                "{}",
+                ipa
+            );
+        }
+    }
+
+    #[test]
+    fn traces_the_candidates() {
+        for &ipa in &["ˈbʌtəɹ", "ʌbɹɔd", "ˈθɪŋkɪŋ", "ˈtʃɪkən"] {
+            for candidate in candidates_with(ipa.chars(), 10, Options::default()) {
+                let mut builder = hangul_builder::Builder::new();
+                let steps =
+                    trace_candidate(&mut builder, ipa.chars(), Options::default(), &candidate);
+                assert!(!steps.is_empty());
+                assert_eq!(
+                    builder.finish().collect::<String>(),
+                    candidate.hangul,
+                    "{}",
+                    ipa
+                );
+            }
+        }
+    }
 }
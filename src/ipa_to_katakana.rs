@@ -0,0 +1,185 @@
+use crate::{
+    katakana_builder::{Builder, Consonant, Vowel},
+    syllabary::{self, is_vowel},
+};
+
+fn is_r(c: Option<char>) -> bool {
+    matches!(c, Some('ɹ') | Some('r'))
+}
+
+/// Transcribes the symbol at the index and returns how many of the symbols
+/// after it were consumed as well.
+fn transcribe(builder: &mut Builder, chars: &[char], index: usize) -> Option<usize> {
+    use Consonant as C;
+
+    let c = chars[index];
+    let previous = index.checked_sub(1).map(|i| chars[i]);
+    let next = chars.get(index + 1).copied();
+
+    let mut skip = 0;
+    match c {
+        'p' => builder.push_consonant(C::P),
+        'b' => builder.push_consonant(C::B),
+        't' if next == Some('ʃ') => {
+            builder.push_consonant(C::Ch);
+            skip = 1;
+        }
+        't' if next == Some('s') => {
+            builder.push_consonant(C::Ts);
+            skip = 1;
+        }
+        't' => builder.push_consonant(C::T),
+        'd' if next == Some('ʒ') => {
+            builder.push_consonant(C::J);
+            skip = 1;
+        }
+        'd' if next == Some('z') => {
+            builder.push_consonant(C::Dz);
+            skip = 1;
+        }
+        'd' => builder.push_consonant(C::D),
+        'k' => builder.push_consonant(C::K),
+        'ɡ' | 'g' => builder.push_consonant(C::G),
+        'f' => builder.push_consonant(C::F),
+        'v' => builder.push_consonant(C::V),
+        'θ' | 's' => builder.push_consonant(C::S),
+        'ð' | 'z' => builder.push_consonant(C::Z),
+        'ʃ' => builder.push_consonant(C::Sh),
+        'ʒ' | 'ʤ' => builder.push_consonant(C::J),
+        'ʧ' => builder.push_consonant(C::Ch),
+        'ʦ' => builder.push_consonant(C::Ts),
+        'h' => builder.push_consonant(C::H),
+        'm' => builder.push_consonant(C::M),
+        'n' => builder.push_consonant(C::N),
+        'ŋ' => {
+            builder.push_consonant(C::N);
+            if !matches!(next, Some('k') | Some('ɡ') | Some('g')) {
+                builder.push_consonant(C::G);
+            }
+        }
+        'l' => builder.push_consonant(C::R),
+        'ɹ' | 'r' if matches!(next, Some(next) if is_vowel(next)) => builder.push_consonant(C::R),
+        // An r that closes a syllable lengthens the vowel, except after front
+        // and close vowels, where it becomes ア, as in ヒア.
+        'ɹ' | 'r' => match previous {
+            Some('ɪ') | Some('i') | Some('ɛ') | Some('e') | Some('ʊ') | Some('u') => {
+                builder.push_vowel(Vowel::A)
+            }
+            Some(previous) if is_vowel(previous) => builder.push_long_vowel(),
+            _ => builder.push_consonant(C::R),
+        },
+        'w' => builder.push_consonant(C::W),
+        'j' => builder.push_consonant(C::Y),
+        'a' => {
+            builder.push_vowel(Vowel::A);
+            match next {
+                Some('ɪ') => {
+                    builder.push_vowel(Vowel::I);
+                    skip = 1;
+                }
+                Some('ʊ') => {
+                    builder.push_vowel(Vowel::U);
+                    skip = 1;
+                }
+                _ => {}
+            }
+        }
+        // After ク and グ, it's written キャ and ギャ, as in キャット.
+        'æ' => {
+            if matches!(previous, Some('k') | Some('ɡ') | Some('g')) {
+                builder.push_consonant(C::Y);
+            }
+            builder.push_vowel(Vowel::A);
+        }
+        'ʌ' | 'ɐ' | 'ə' => builder.push_vowel(Vowel::A),
+        // The long ɑː of PALM and the ɑ before r are written アー, as in
+        // カー, while the short one of LOT is written オ.
+        'ɑ' if is_r(next) || next == Some('ː') => builder.push_vowel(Vowel::A),
+        'ɑ' | 'ɒ' => builder.push_vowel(Vowel::O),
+        'ɔ' => {
+            builder.push_vowel(Vowel::O);
+            match next {
+                Some('ɪ') => {
+                    builder.push_vowel(Vowel::I);
+                    skip = 1;
+                }
+                None => builder.push_long_vowel(),
+                _ => {}
+            }
+        }
+        'ɛ' => builder.push_vowel(Vowel::E),
+        'e' => {
+            builder.push_vowel(Vowel::E);
+            if next == Some('ɪ') {
+                builder.push_long_vowel();
+                skip = 1;
+            }
+        }
+        'ɪ' => builder.push_vowel(Vowel::I),
+        'i' => {
+            builder.push_vowel(Vowel::I);
+            if !is_r(next) && next != Some('ə') {
+                builder.push_long_vowel();
+            }
+        }
+        'o' => {
+            builder.push_vowel(Vowel::O);
+            if next == Some('ʊ') {
+                builder.push_long_vowel();
+                skip = 1;
+            }
+        }
+        'ʊ' => builder.push_vowel(Vowel::U),
+        'u' => {
+            builder.push_vowel(Vowel::U);
+            if !is_r(next) {
+                builder.push_long_vowel();
+            }
+        }
+        'ɝ' | 'ɜ' | 'ɚ' => {
+            builder.push_vowel(Vowel::A);
+            builder.push_long_vowel();
+        }
+        'ː' => builder.push_long_vowel(),
+        'ˈ' | '\'' | 'ˌ' => {} // Explicitly ignored
+        '|' => builder.push_space(),
+        _ => return None,
+    }
+    Some(skip)
+}
+
+/// Transcribes the pronunciation into katakana, the way English loanwords
+/// are written in Japanese.
+pub fn convert(builder: &mut Builder, phonetics: impl IntoIterator<Item = char>) -> &str {
+    syllabary::transcribe(builder, phonetics, transcribe);
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcribes_the_vowels() {
+        let cases = [
+            // PALM and START are アー, LOT is オ.
+            ("kɑː", "カー"),
+            ("kɑɹ", "カー"),
+            ("fɑːðə", "ファーザ"),
+            ("hɒt", "ホット"),
+            ("hɑt", "ホット"),
+            ("kæt", "キャット"),
+            ("boʊt", "ボート"),
+            ("biɹ", "ビア"),
+            ("ˈwɔːtə", "ウォータ"),
+        ];
+        for &(ipa, katakana) in &cases {
+            assert_eq!(
+                convert(&mut Builder::new(), ipa.chars()),
+                katakana,
+                "{}",
+                ipa
+            );
+        }
+    }
+}
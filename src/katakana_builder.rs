@@ -0,0 +1,267 @@
+use crate::syllabary::SyllabaryBuilder;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Consonant {
+    K,
+    G,
+    S,
+    Z,
+    T,
+    D,
+    N,
+    H,
+    B,
+    P,
+    M,
+    /// The palatal glide. Following another consonant, it palatalizes it,
+    /// as in キャ.
+    Y,
+    R,
+    W,
+    F,
+    V,
+    Sh,
+    J,
+    Ch,
+    Ts,
+    /// The affricate in words like "kids", written like ズ.
+    Dz,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Vowel {
+    A,
+    I,
+    U,
+    E,
+    O,
+}
+
+impl Vowel {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The kana for the consonant followed by ア, イ, ウ, エ and オ.
+fn row(cons: Option<Consonant>) -> [&'static str; 5] {
+    use Consonant::*;
+
+    match cons {
+        None => ["ア", "イ", "ウ", "エ", "オ"],
+        Some(K) => ["カ", "キ", "ク", "ケ", "コ"],
+        Some(G) => ["ガ", "ギ", "グ", "ゲ", "ゴ"],
+        Some(S) => ["サ", "シ", "ス", "セ", "ソ"],
+        Some(Z) | Some(Dz) => ["ザ", "ジ", "ズ", "ゼ", "ゾ"],
+        Some(T) => ["タ", "ティ", "トゥ", "テ", "ト"],
+        Some(D) => ["ダ", "ディ", "ドゥ", "デ", "ド"],
+        Some(N) => ["ナ", "ニ", "ヌ", "ネ", "ノ"],
+        Some(H) => ["ハ", "ヒ", "フ", "ヘ", "ホ"],
+        Some(B) => ["バ", "ビ", "ブ", "ベ", "ボ"],
+        Some(P) => ["パ", "ピ", "プ", "ペ", "ポ"],
+        Some(M) => ["マ", "ミ", "ム", "メ", "モ"],
+        Some(Y) => ["ヤ", "イ", "ユ", "イェ", "ヨ"],
+        Some(R) => ["ラ", "リ", "ル", "レ", "ロ"],
+        Some(W) => ["ワ", "ウィ", "ウ", "ウェ", "ウォ"],
+        Some(F) => ["ファ", "フィ", "フ", "フェ", "フォ"],
+        Some(V) => ["ヴァ", "ヴィ", "ヴ", "ヴェ", "ヴォ"],
+        Some(Sh) => ["シャ", "シ", "シュ", "シェ", "ショ"],
+        Some(J) => ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"],
+        Some(Ch) => ["チャ", "チ", "チュ", "チェ", "チョ"],
+        Some(Ts) => ["ツァ", "ツィ", "ツ", "ツェ", "ツォ"],
+    }
+}
+
+/// The kana that a small ャ, ュ or ョ attaches to when the consonant is
+/// palatalized.
+fn palatal_base(cons: Consonant) -> &'static str {
+    match cons {
+        Consonant::T => "テ",
+        Consonant::D => "デ",
+        Consonant::F | Consonant::H => "フ",
+        Consonant::V => "ヴ",
+        Consonant::W => "ウ",
+        Consonant::Ts => "ツ",
+        cons => row(Some(cons))[Vowel::I.index()],
+    }
+}
+
+/// The vowel that is inserted after a consonant that isn't followed by one.
+fn epenthetic_vowel(cons: Consonant) -> Vowel {
+    match cons {
+        Consonant::T | Consonant::D => Vowel::O,
+        Consonant::Ch | Consonant::J => Vowel::I,
+        _ => Vowel::U,
+    }
+}
+
+/// Whether the consonant is preceded by ッ when it ends a word after a short
+/// vowel, as in ベッド or キャッシュ.
+fn takes_sokuon(cons: Consonant) -> bool {
+    use Consonant::*;
+
+    matches!(cons, K | G | T | D | P | Ch | J | Sh | Ts | Dz)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Mora {
+    StartOfWord,
+    ShortVowel,
+    Other,
+}
+
+/// Builds katakana following the conventions for English loanwords.
+#[derive(Clone)]
+pub struct Builder {
+    text: String,
+    pending_cons: Option<Consonant>,
+    palatalized: bool,
+    last: Mora,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            pending_cons: None,
+            palatalized: false,
+            last: Mora::StartOfWord,
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_start_of_word(&self) -> bool {
+        self.last == Mora::StartOfWord && self.pending_cons.is_none()
+    }
+
+    /// Pushes a consonant. It's written once it's known whether a vowel
+    /// follows it. If none does, an epenthetic vowel is inserted, unless the
+    /// consonant is a moraic nasal.
+    pub fn push_consonant(&mut self, cons: Consonant) {
+        if cons == Consonant::Y && !self.palatalized {
+            if let Some(earlier_cons) = self.pending_cons {
+                if earlier_cons != Consonant::Y {
+                    self.palatalized = true;
+                    return;
+                }
+            }
+        }
+        self.flush(Some(cons));
+        self.pending_cons = Some(cons);
+    }
+
+    pub fn push_vowel(&mut self, vowel: Vowel) {
+        let cons = self.pending_cons.take();
+        match cons {
+            Some(cons) if self.palatalized => {
+                self.palatalized = false;
+                match (cons, vowel) {
+                    (Consonant::Sh, _) | (Consonant::J, _) | (Consonant::Ch, _) | (_, Vowel::I) => {
+                        self.text.push_str(row(Some(cons))[vowel.index()]);
+                    }
+                    _ => {
+                        self.text.push_str(palatal_base(cons));
+                        self.text.push(match vowel {
+                            Vowel::A => 'ャ',
+                            Vowel::U => 'ュ',
+                            Vowel::E => 'ェ',
+                            _ => 'ョ',
+                        });
+                    }
+                }
+            }
+            cons => self.text.push_str(row(cons)[vowel.index()]),
+        }
+        self.last = Mora::ShortVowel;
+    }
+
+    /// Lengthens the vowel that was just pushed with ー.
+    pub fn push_long_vowel(&mut self) {
+        if self.pending_cons.is_none() && self.last == Mora::ShortVowel {
+            self.text.push('ー');
+            self.last = Mora::Other;
+        }
+    }
+
+    /// Writes the pending consonant, now that it's known that it isn't
+    /// followed by a vowel. `next` is the consonant that follows it, if any.
+    fn flush(&mut self, next: Option<Consonant>) {
+        let cons = match self.pending_cons.take() {
+            Some(cons) => cons,
+            None => return,
+        };
+
+        if self.palatalized {
+            self.palatalized = false;
+            self.text.push_str(row(Some(cons))[Vowel::I.index()]);
+        } else if cons == Consonant::N
+            || cons == Consonant::M
+                && matches!(
+                    next,
+                    Some(Consonant::B) | Some(Consonant::P) | Some(Consonant::M)
+                )
+        {
+            self.text.push('ン');
+        } else {
+            if next.is_none() && self.last == Mora::ShortVowel && takes_sokuon(cons) {
+                self.text.push('ッ');
+            }
+            self.text
+                .push_str(row(Some(cons))[epenthetic_vowel(cons).index()]);
+        }
+        self.last = Mora::Other;
+    }
+
+    pub fn push_space(&mut self) {
+        self.flush(None);
+        self.text.push(' ');
+        self.last = Mora::StartOfWord;
+    }
+
+    /// Finishes the text and returns it.
+    pub fn finish(&mut self) -> &str {
+        self.flush(None);
+        &self.text
+    }
+
+    pub fn restart(&mut self) {
+        self.text.clear();
+        self.pending_cons = None;
+        self.palatalized = false;
+        self.last = Mora::StartOfWord;
+    }
+}
+
+impl SyllabaryBuilder for Builder {
+    type Consonant = Consonant;
+    type Vowel = Vowel;
+
+    fn is_start_of_word(&self) -> bool {
+        Builder::is_start_of_word(self)
+    }
+
+    fn push_consonant(&mut self, cons: Consonant) {
+        Builder::push_consonant(self, cons)
+    }
+
+    fn push_vowel(&mut self, vowel: Vowel) {
+        Builder::push_vowel(self, vowel)
+    }
+
+    fn push_space(&mut self) {
+        Builder::push_space(self)
+    }
+
+    fn finish_text(&mut self) -> String {
+        self.finish().to_owned()
+    }
+
+    fn restart(&mut self) {
+        Builder::restart(self)
+    }
+}
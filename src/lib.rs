@@ -1,6 +1,23 @@
+pub mod accent;
 pub mod arpabet;
+pub mod batch;
+pub mod document;
+pub mod evaluation;
+pub mod fidelity;
 pub mod hangul_builder;
+pub mod hangul_to_ipa;
 pub mod ipa_to_hangul;
+pub mod ipa_to_katakana;
+pub mod katakana_builder;
+pub mod lookup_cache;
+pub mod pronunciation_source;
+pub mod romanization;
+pub mod syllabary;
+pub mod syllabification;
+pub mod user_dictionary;
+
+#[cfg(feature = "wiktionary")]
+pub mod wiktionary;
 
 #[cfg(feature = "lookup")]
 pub mod word_lookup;
@@ -0,0 +1,303 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use unicase::UniCase;
+
+use crate::pronunciation_source::{Pronunciation, PronunciationSource};
+
+/// How long cached lookups stay valid by default.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const HEADER: &str = "# phonetics-to-hangul lookup cache";
+
+/// Marks the lines of lookups that only fetched the first pronunciation.
+const FIRST_ONLY: &str = "first";
+
+struct Entry {
+    /// Seconds since the Unix epoch.
+    fetched: u64,
+    /// Empty if the source didn't know the word, so it isn't looked up again
+    /// either.
+    pronunciations: Vec<Pronunciation>,
+    /// Whether all the pronunciations were fetched, not just the first one.
+    all: bool,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The results of earlier lookups, keyed by language and word and stored in
+/// a file. Each line holds the language, the word, when it was looked up and
+/// one of the pronunciations found along with its spelling and part of
+/// speech, separated by tabs. Lookups of only the first pronunciation end
+/// with an additional `first`.
+pub struct Cache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: HashMap<(String, UniCase<String>), Entry>,
+}
+
+impl Cache {
+    /// Opens the cache stored at the path. If the file doesn't exist yet,
+    /// the cache starts out empty. Lines that can't be read are dropped.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let txt = match fs::read_to_string(&path) {
+            Ok(txt) => txt,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).context("Failed reading the lookup cache."),
+        };
+
+        let mut entries = HashMap::new();
+        for line in txt.lines().filter(|l| !l.starts_with('#')) {
+            let mut fields = line.split('\t');
+            let (language, word, fetched, spelling, part_of_speech, ipa) = match (
+                fields.next(),
+                fields.next(),
+                fields.next().and_then(|f| f.parse().ok()),
+                fields.next(),
+                fields.next(),
+                fields.next(),
+            ) {
+                (Some(l), Some(w), Some(f), Some(s), Some(p), Some(i)) => (l, w, f, s, p, i),
+                _ => continue,
+            };
+
+            let all = fields.next() != Some(FIRST_ONLY);
+
+            let entry = entries
+                .entry((language.to_owned(), UniCase::new(word.to_owned())))
+                .or_insert_with(|| Entry {
+                    fetched,
+                    pronunciations: Vec::new(),
+                    all,
+                });
+            if !ipa.is_empty() {
+                entry.pronunciations.push(Pronunciation {
+                    word: spelling.to_owned(),
+                    ipa: ipa.to_owned(),
+                    part_of_speech: Some(part_of_speech)
+                        .filter(|p| !p.is_empty())
+                        .map(str::to_owned),
+                });
+            }
+        }
+
+        Ok(Self {
+            path,
+            ttl: DEFAULT_TTL,
+            entries,
+        })
+    }
+
+    /// Sets how long lookups stay valid. Defaults to [`DEFAULT_TTL`].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the cached pronunciations of the word, unless they are missing
+    /// or expired. An empty list means that the word wasn't found. With
+    /// `all`, a lookup of only the first pronunciation counts as missing.
+    pub fn get(&self, word: &str, language: &str, all: bool) -> Option<&[Pronunciation]> {
+        let entry = self.entry(word, language, all)?;
+        if now().saturating_sub(entry.fetched) > self.ttl.as_secs() {
+            return None;
+        }
+        Some(&entry.pronunciations)
+    }
+
+    /// Returns the cached pronunciations of the word like [`Cache::get`], but
+    /// also if they are expired. That's still better than nothing when the
+    /// source can't be asked.
+    pub fn get_expired(&self, word: &str, language: &str, all: bool) -> Option<&[Pronunciation]> {
+        Some(&self.entry(word, language, all)?.pronunciations)
+    }
+
+    fn entry(&self, word: &str, language: &str, all: bool) -> Option<&Entry> {
+        self.entries
+            .get(&(language.to_owned(), UniCase::new(word.to_owned())))
+            .filter(|entry| entry.all || !all)
+    }
+
+    /// Remembers the pronunciations of the word. Without `all`, only the
+    /// first pronunciation was fetched.
+    pub fn insert(
+        &mut self,
+        word: &str,
+        language: &str,
+        pronunciations: Vec<Pronunciation>,
+        all: bool,
+    ) {
+        // Without a first pronunciation, there are no others either.
+        let all = all || pronunciations.is_empty();
+        self.entries.insert(
+            (language.to_owned(), UniCase::new(word.to_owned())),
+            Entry {
+                fetched: now(),
+                pronunciations,
+                all,
+            },
+        );
+    }
+
+    /// Writes the cache back to its file. Expired lookups are kept, so they
+    /// can still be used without access to the source.
+    pub fn save(&self) -> Result<()> {
+        let mut txt = String::from(HEADER);
+        txt.push('\n');
+
+        for ((language, word), entry) in &self.entries {
+            let marker = if entry.all {
+                String::new()
+            } else {
+                format!("\t{}", FIRST_ONLY)
+            };
+            if entry.pronunciations.is_empty() {
+                txt.push_str(&format!(
+                    "{}\t{}\t{}\t\t\t{}\n",
+                    language, word, entry.fetched, marker
+                ));
+            }
+            for p in &entry.pronunciations {
+                txt.push_str(&format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}{}\n",
+                    language,
+                    word,
+                    entry.fetched,
+                    p.word,
+                    p.part_of_speech.as_deref().unwrap_or_default(),
+                    p.ipa,
+                    marker,
+                ));
+            }
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("Failed creating the lookup cache's directory.")?;
+        }
+        // Write to a separate file first, so an interrupted write doesn't
+        // lose the whole cache.
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, txt).context("Failed writing the lookup cache.")?;
+        fs::rename(&temp_path, &self.path).context("Failed writing the lookup cache.")?;
+
+        Ok(())
+    }
+}
+
+/// How a [`CachedSource`] uses its cache.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// Serves lookups from the cache and asks the source about anything that
+    /// is missing or expired.
+    Normal,
+    /// Asks the source about every word and updates the cache.
+    Refresh,
+    /// Serves lookups only from the cache, even expired ones, and never asks
+    /// the source.
+    CacheOnly,
+}
+
+/// A source that remembers the results of its lookups in a [`Cache`].
+pub struct CachedSource<S> {
+    source: Option<S>,
+    cache: RefCell<Cache>,
+    mode: Mode,
+    name: String,
+}
+
+impl<S: PronunciationSource> CachedSource<S> {
+    pub fn new(source: S, cache: Cache, mode: Mode) -> Self {
+        let name = format!("{} (cached)", source.name());
+        Self {
+            source: Some(source),
+            cache: RefCell::new(cache),
+            mode,
+            name,
+        }
+    }
+
+    /// Creates a source that only serves what is already in the cache, which
+    /// doesn't need access to the original source at all.
+    pub fn cache_only(cache: Cache) -> Self {
+        Self {
+            source: None,
+            cache: RefCell::new(cache),
+            mode: Mode::CacheOnly,
+            name: "cache".to_owned(),
+        }
+    }
+
+    /// Writes the cache back to its file.
+    pub fn save(&self) -> Result<()> {
+        self.cache.borrow().save()
+    }
+
+    /// Looks up the first or all the pronunciations of the word, in the
+    /// cache or the source depending on the mode. Only what was asked for is
+    /// fetched, as every additional pronunciation may cost a request.
+    fn cached_look_up(&self, word: &str, language: &str, all: bool) -> Result<Vec<Pronunciation>> {
+        let cached = match self.mode {
+            Mode::Normal => self
+                .cache
+                .borrow()
+                .get(word, language, all)
+                .map(<[_]>::to_vec),
+            Mode::Refresh => None,
+            Mode::CacheOnly => self
+                .cache
+                .borrow()
+                .get_expired(word, language, all)
+                .map(<[_]>::to_vec),
+        };
+        if let Some(pronunciations) = cached {
+            return Ok(pronunciations);
+        }
+
+        let source = match (&self.source, self.mode) {
+            (Some(source), Mode::Normal) | (Some(source), Mode::Refresh) => source,
+            _ => return Ok(Vec::new()),
+        };
+
+        let pronunciations = if all {
+            source.look_up_all(word, language)?
+        } else {
+            source.look_up(word, language)?.into_iter().collect()
+        };
+        self.cache
+            .borrow_mut()
+            .insert(word, language, pronunciations.clone(), all);
+        Ok(pronunciations)
+    }
+}
+
+impl<S: PronunciationSource> PronunciationSource for CachedSource<S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        Ok(self
+            .cached_look_up(word, language, false)?
+            .into_iter()
+            .next())
+    }
+
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        self.cached_look_up(word, language, true)
+    }
+}
@@ -0,0 +1,157 @@
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+
+/// The pronunciation of a word in IPA.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pronunciation {
+    /// The word as the source spells it, which may differ from the word that
+    /// was looked up, e.g. in its capitalization.
+    pub word: String,
+    pub ipa: String,
+    /// The part of speech, such as `noun`, if the source distinguishes the
+    /// pronunciations by it.
+    pub part_of_speech: Option<String>,
+}
+
+/// Something that knows how words are pronounced, such as a dictionary.
+pub trait PronunciationSource {
+    /// A short name of the source to show to the user.
+    fn name(&self) -> &str;
+
+    /// Looks up the pronunciation of the word in the language, given as an
+    /// ISO 639-1 code. Returns `None` if the source doesn't know the word.
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>>;
+
+    /// Looks up all the pronunciations the source knows for the word, such
+    /// as the ones for different parts of speech. The list is empty if the
+    /// source doesn't know the word.
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        Ok(self.look_up(word, language)?.into_iter().collect())
+    }
+}
+
+impl<S: PronunciationSource + ?Sized> PronunciationSource for &S {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        (**self).look_up(word, language)
+    }
+
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        (**self).look_up_all(word, language)
+    }
+}
+
+impl<S: PronunciationSource + ?Sized> PronunciationSource for Rc<S> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        (**self).look_up(word, language)
+    }
+
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        (**self).look_up_all(word, language)
+    }
+}
+
+impl<S: PronunciationSource + ?Sized> PronunciationSource for Box<S> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        (**self).look_up(word, language)
+    }
+
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        (**self).look_up_all(word, language)
+    }
+}
+
+/// A pronunciation along with the name of the source it came from.
+#[derive(Clone, Debug)]
+pub struct Resolution<'a> {
+    pub source: &'a str,
+    pub pronunciation: Pronunciation,
+}
+
+/// All the pronunciations a source knows, along with its name.
+#[derive(Clone, Debug)]
+pub struct Resolutions<'a> {
+    pub source: &'a str,
+    pub pronunciations: Vec<Pronunciation>,
+}
+
+/// Tries multiple sources in order until one of them knows the word.
+#[derive(Default)]
+pub struct Resolver<'a> {
+    sources: Vec<Box<dyn PronunciationSource + 'a>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a source that is tried after all the sources added so far.
+    pub fn push(&mut self, source: impl PronunciationSource + 'a) {
+        self.sources.push(Box::new(source));
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().map(|source| source.name())
+    }
+
+    /// Looks up the word in each source in order and returns the first
+    /// pronunciation found. A source that fails doesn't stop the search, but
+    /// its error is returned if none of the other sources know the word
+    /// either.
+    pub fn resolve(&self, word: &str, language: &str) -> Result<Resolution<'_>> {
+        self.find(|source| source.look_up(word, language))
+            .map(|(source, pronunciation)| Resolution {
+                source,
+                pronunciation,
+            })
+    }
+
+    /// Looks up the word in each source in order, like [`resolve`], but
+    /// returns all the pronunciations of the first source that knows it.
+    ///
+    /// [`resolve`]: Resolver::resolve
+    pub fn resolve_all(&self, word: &str, language: &str) -> Result<Resolutions<'_>> {
+        self.find(|source| {
+            let pronunciations = source.look_up_all(word, language)?;
+            Ok(Some(pronunciations).filter(|p| !p.is_empty()))
+        })
+        .map(|(source, pronunciations)| Resolutions {
+            source,
+            pronunciations,
+        })
+    }
+
+    fn find<T>(
+        &self,
+        mut look_up: impl FnMut(&dyn PronunciationSource) -> Result<Option<T>>,
+    ) -> Result<(&str, T)> {
+        let mut first_error = None;
+
+        for source in &self.sources {
+            match look_up(&**source) {
+                Ok(Some(found)) => return Ok((source.name(), found)),
+                Ok(None) => {}
+                Err(e) => {
+                    if first_error.is_none() {
+                        first_error = Some(e.context(format!("{} failed.", source.name())));
+                    }
+                }
+            }
+        }
+
+        Err(first_error.unwrap_or_else(|| anyhow!("None of the sources contain the word.")))
+    }
+}
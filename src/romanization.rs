@@ -0,0 +1,293 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    hangul_builder::{Consonant, FinalConsonant, Syllable, Vowel},
+    hangul_to_ipa::{self, Sound, Token},
+};
+
+/// The romanization systems for Korean.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum System {
+    /// The official Revised Romanization of Korean (국어의 로마자 표기법).
+    #[default]
+    RevisedRomanization,
+    /// The McCune–Reischauer system.
+    McCuneReischauer,
+    /// The Yale system, which transliterates the spelling letter by letter.
+    Yale,
+}
+
+impl FromStr for System {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "rr" | "revised" => System::RevisedRomanization,
+            "mr" | "mccune-reischauer" => System::McCuneReischauer,
+            "yale" => System::Yale,
+            _ => bail!("Unknown romanization system `{}`.", s),
+        })
+    }
+}
+
+fn vowel(vowel: Vowel, system: System) -> &'static str {
+    let mr = system == System::McCuneReischauer;
+    match vowel {
+        Vowel::A => "a",
+        Vowel::Ae => "ae",
+        Vowel::Ya => "ya",
+        Vowel::Yae => "yae",
+        Vowel::Eo if mr => "ŏ",
+        Vowel::Eo => "eo",
+        Vowel::E => "e",
+        Vowel::Yeo if mr => "yŏ",
+        Vowel::Yeo => "yeo",
+        Vowel::Ye => "ye",
+        Vowel::O => "o",
+        Vowel::Wa => "wa",
+        Vowel::Wae => "wae",
+        Vowel::Oe => "oe",
+        Vowel::Yo => "yo",
+        Vowel::U => "u",
+        Vowel::Wo if mr => "wŏ",
+        Vowel::Wo => "wo",
+        Vowel::We => "we",
+        Vowel::Wi => "wi",
+        Vowel::Yu => "yu",
+        Vowel::Eu if mr => "ŭ",
+        Vowel::Eu => "eu",
+        Vowel::Ui if mr => "ŭi",
+        Vowel::Ui => "ui",
+        Vowel::I => "i",
+    }
+}
+
+fn revised_initial(cons: Consonant, after_l: bool) -> &'static str {
+    match cons {
+        Consonant::G => "g",
+        Consonant::Gg => "kk",
+        Consonant::K => "k",
+        Consonant::D => "d",
+        Consonant::Dd => "tt",
+        Consonant::T => "t",
+        Consonant::B => "b",
+        Consonant::Bb => "pp",
+        Consonant::P => "p",
+        Consonant::J => "j",
+        Consonant::Jj => "jj",
+        Consonant::Ch => "ch",
+        Consonant::S => "s",
+        Consonant::Ss => "ss",
+        Consonant::H => "h",
+        Consonant::N => "n",
+        Consonant::M => "m",
+        Consonant::L if after_l => "l",
+        Consonant::L => "r",
+        Consonant::Ng => "",
+    }
+}
+
+fn mccune_reischauer_initial(cons: Consonant, voiced: bool, after_l: bool) -> &'static str {
+    match cons {
+        Consonant::G if voiced => "g",
+        Consonant::G => "k",
+        Consonant::Gg => "kk",
+        Consonant::K => "k'",
+        Consonant::D if voiced => "d",
+        Consonant::D => "t",
+        Consonant::Dd => "tt",
+        Consonant::T => "t'",
+        Consonant::B if voiced => "b",
+        Consonant::B => "p",
+        Consonant::Bb => "pp",
+        Consonant::P => "p'",
+        Consonant::J if voiced => "j",
+        Consonant::J => "ch",
+        Consonant::Jj => "tch",
+        Consonant::Ch => "ch'",
+        Consonant::S => "s",
+        Consonant::Ss => "ss",
+        Consonant::H => "h",
+        Consonant::N => "n",
+        Consonant::M => "m",
+        Consonant::L if after_l => "l",
+        Consonant::L => "r",
+        Consonant::Ng => "",
+    }
+}
+
+fn pronounced_final(cons: Consonant) -> &'static str {
+    match cons {
+        Consonant::G => "k",
+        Consonant::D => "t",
+        Consonant::B => "p",
+        Consonant::N => "n",
+        Consonant::M => "m",
+        Consonant::Ng => "ng",
+        _ => "l",
+    }
+}
+
+fn romanize_sounds(romanized: &mut String, sounds: &[Sound], system: System) {
+    let mut previous: Option<Sound> = None;
+    for sound in sounds {
+        let previous_final = previous.and_then(|p| p.final_cons);
+        let after_l = previous_final == Some(Consonant::L);
+
+        if system == System::McCuneReischauer {
+            // The apostrophe keeps ㄴ followed by ㄱ apart from ㅇ.
+            if previous_final == Some(Consonant::N) && sound.initial == Consonant::G {
+                romanized.push('\'');
+            }
+            let voiced = match (previous, previous_final) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(_), Some(cons)) => {
+                    matches!(
+                        cons,
+                        Consonant::N | Consonant::M | Consonant::Ng | Consonant::L
+                    )
+                }
+            };
+            romanized.push_str(mccune_reischauer_initial(sound.initial, voiced, after_l));
+        } else {
+            romanized.push_str(revised_initial(sound.initial, after_l));
+        }
+
+        romanized.push_str(vowel(sound.vowel, system));
+        if let Some(cons) = sound.final_cons {
+            romanized.push_str(pronounced_final(cons));
+        }
+        previous = Some(*sound);
+    }
+}
+
+fn yale_consonant(cons: Consonant) -> &'static str {
+    match cons {
+        Consonant::G => "k",
+        Consonant::Gg => "kk",
+        Consonant::K => "kh",
+        Consonant::D => "t",
+        Consonant::Dd => "tt",
+        Consonant::T => "th",
+        Consonant::B => "p",
+        Consonant::Bb => "pp",
+        Consonant::P => "ph",
+        Consonant::J => "c",
+        Consonant::Jj => "cc",
+        Consonant::Ch => "ch",
+        Consonant::S => "s",
+        Consonant::Ss => "ss",
+        Consonant::H => "h",
+        Consonant::N => "n",
+        Consonant::M => "m",
+        Consonant::L => "l",
+        Consonant::Ng => "ng",
+    }
+}
+
+fn yale_vowel(vowel: Vowel) -> &'static str {
+    match vowel {
+        Vowel::A => "a",
+        Vowel::Ae => "ay",
+        Vowel::Ya => "ya",
+        Vowel::Yae => "yay",
+        Vowel::Eo => "e",
+        Vowel::E => "ey",
+        Vowel::Yeo => "ye",
+        Vowel::Ye => "yey",
+        Vowel::O => "o",
+        Vowel::Wa => "wa",
+        Vowel::Wae => "way",
+        Vowel::Oe => "oy",
+        Vowel::Yo => "yo",
+        Vowel::U => "wu",
+        Vowel::Wo => "we",
+        Vowel::We => "wey",
+        Vowel::Wi => "wi",
+        Vowel::Yu => "yu",
+        Vowel::Eu => "u",
+        Vowel::Ui => "uy",
+        Vowel::I => "i",
+    }
+}
+
+fn romanize_yale(romanized: &mut String, syllable: Syllable) {
+    let initial = syllable.initial_consonant();
+    if initial != Consonant::Ng {
+        romanized.push_str(yale_consonant(initial));
+    }
+    romanized.push_str(match (initial, syllable.vowel()) {
+        // The labial consonants already imply the rounding of ㅜ.
+        (Consonant::B, Vowel::U)
+        | (Consonant::Bb, Vowel::U)
+        | (Consonant::P, Vowel::U)
+        | (Consonant::M, Vowel::U) => "u",
+        (_, vowel) => yale_vowel(vowel),
+    });
+    match syllable.final_consonant() {
+        Some(FinalConsonant::Single(cons)) => romanized.push_str(yale_consonant(cons)),
+        Some(FinalConsonant::Cluster(first, second)) => {
+            romanized.push_str(yale_consonant(first));
+            romanized.push_str(yale_consonant(second));
+        }
+        None => {}
+    }
+}
+
+/// Romanizes 한글 with the given system. The Revised Romanization and
+/// McCune–Reischauer follow the pronunciation, so the sound changes between
+/// syllables are applied, except for tensing. Yale transliterates the
+/// spelling. Characters that aren't 한글 syllables are kept as is.
+pub fn romanize(hangul: &str, system: System) -> String {
+    let mut romanized = String::new();
+
+    if system == System::Yale {
+        for c in hangul.nfc() {
+            match Syllable::from_char(c) {
+                Some(syllable) => romanize_yale(&mut romanized, syllable),
+                None => romanized.push(c),
+            }
+        }
+    } else {
+        for token in hangul_to_ipa::pronounce(hangul, false) {
+            match token {
+                Token::Word(sounds) => romanize_sounds(&mut romanized, &sounds, system),
+                Token::Other(c) => romanized.push(c),
+            }
+        }
+    }
+
+    romanized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romanizes_with_each_system() {
+        let cases = [
+            // 유음화 is pronounced, but Yale follows the spelling.
+            ("신라", "silla", "silla", "sinla"),
+            // Consonant assimilation.
+            ("독립문", "dongnimmun", "tongnimmun", "toklipmun"),
+            ("종로", "jongno", "chongno", "conglo"),
+            // Voicing and aspiration.
+            ("부산", "busan", "pusan", "pusan"),
+            ("김치", "gimchi", "kimch'i", "kimchi"),
+            ("같이", "gachi", "kach'i", "kathi"),
+            // ㅓ and ㅡ.
+            ("어른", "eoreun", "ŏrŭn", "elun"),
+            ("한글", "hangeul", "han'gŭl", "hankul"),
+        ];
+        for &(hangul, rr, mr, yale) in &cases {
+            assert_eq!(romanize(hangul, System::RevisedRomanization), rr);
+            assert_eq!(romanize(hangul, System::McCuneReischauer), mr);
+            assert_eq!(romanize(hangul, System::Yale), yale);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+/// Whether the IPA symbol is a vowel.
+pub(crate) fn is_vowel(c: char) -> bool {
+    matches!(
+        c,
+        'a' | 'æ'
+            | 'ɐ'
+            | 'ʌ'
+            | 'ə'
+            | 'ɑ'
+            | 'ɒ'
+            | 'ɔ'
+            | 'ɛ'
+            | 'e'
+            | 'ɪ'
+            | 'i'
+            | 'o'
+            | 'ʊ'
+            | 'u'
+            | 'ɝ'
+            | 'ɜ'
+            | 'ɚ'
+    )
+}
+
+/// Assembles text in a syllabic script, one sound at a time. How the sounds
+/// are grouped into syllables or moras is up to the script.
+pub trait SyllabaryBuilder {
+    type Consonant: Copy;
+    type Vowel: Copy;
+
+    /// Whether nothing has been pushed since the start of the current word.
+    fn is_start_of_word(&self) -> bool;
+
+    fn push_consonant(&mut self, cons: Self::Consonant);
+
+    fn push_vowel(&mut self, vowel: Self::Vowel);
+
+    fn push_space(&mut self);
+
+    /// Finishes the text and returns it.
+    fn finish_text(&mut self) -> String;
+
+    fn restart(&mut self);
+}
+
+/// Feeds a pronunciation to the builder symbol by symbol. `transcribe` is
+/// called with all the symbols and the index of the current one. It returns
+/// how many of the symbols that follow it consumed as well, or `None` if the
+/// symbol is unknown, in which case it is skipped.
+pub fn transcribe<B, F>(
+    builder: &mut B,
+    phonetics: impl IntoIterator<Item = char>,
+    mut transcribe: F,
+) where
+    B: SyllabaryBuilder,
+    F: FnMut(&mut B, &[char], usize) -> Option<usize>,
+{
+    builder.restart();
+
+    let chars = phonetics.into_iter().collect::<Vec<_>>();
+    let mut index = 0;
+
+    while index < chars.len() {
+        index += transcribe(builder, &chars, index).unwrap_or(0) + 1;
+    }
+}
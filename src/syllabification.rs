@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::syllabary::is_vowel;
+
+/// The part of its syllable that an IPA symbol belongs to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Role {
+    Onset,
+    Nucleus,
+    Coda {
+        /// Whether the consonant directly follows the nucleus.
+        first: bool,
+        /// Whether the nucleus is a short vowel, such as the one in `cat`.
+        short_vowel: bool,
+    },
+    /// Stress and length marks, syllable and word boundaries and symbols
+    /// that aren't sounds.
+    Other,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::Onset => "onset",
+            Role::Nucleus => "nucleus",
+            Role::Coda { .. } => "coda",
+            Role::Other => "other",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Kind {
+    Vowel,
+    Consonant,
+    /// An explicit syllable boundary, such as a stress mark.
+    Mark,
+    WordBoundary,
+    Other,
+}
+
+/// A sound along with the symbols it's written with.
+struct Unit {
+    start: usize,
+    len: usize,
+    kind: Kind,
+}
+
+fn is_consonant(c: char) -> bool {
+    matches!(
+        c,
+        'p' | 'b'
+            | 't'
+            | 'd'
+            | 'k'
+            | 'g'
+            | 'ɡ'
+            | 'f'
+            | 'v'
+            | 'θ'
+            | 'ð'
+            | 's'
+            | 'z'
+            | 'ʃ'
+            | 'ʒ'
+            | 'h'
+            | 'm'
+            | 'n'
+            | 'ŋ'
+            | 'l'
+            | 'ɹ'
+            | 'r'
+            | 'w'
+            | 'ʦ'
+    )
+}
+
+fn units(chars: &[char]) -> Vec<Unit> {
+    let mut units = Vec::new();
+    let mut index = 0;
+
+    while let Some(&c) = chars.get(index) {
+        let next = chars.get(index + 1).copied();
+        let (len, kind) = match (c, next) {
+            ('t', Some('ʃ')) | ('d', Some('ʒ')) => (2, Kind::Consonant),
+            // The glides are transcribed along with the vowel after them.
+            ('j', _) => (1, Kind::Vowel),
+            ('w', Some(next)) if is_vowel(next) => (1, Kind::Vowel),
+            ('ː', _) => (1, Kind::Vowel),
+            _ if is_vowel(c) || c == 'y' => (1, Kind::Vowel),
+            _ if is_consonant(c) => (1, Kind::Consonant),
+            ('ˈ', _) | ('ˌ', _) | ('\'', _) | ('.', _) => (1, Kind::Mark),
+            ('|', _) | (' ', _) => (1, Kind::WordBoundary),
+            _ => (1, Kind::Other),
+        };
+        units.push(Unit {
+            start: index,
+            len,
+            kind,
+        });
+        index += len;
+    }
+
+    units
+}
+
+/// Whether the consonants can start a syllable in English.
+fn is_onset(consonants: &[&[char]]) -> bool {
+    let is_liquid = |c: &[char]| matches!(c, ['l'] | ['ɹ'] | ['r']);
+    let is_rhotic = |c: &[char]| matches!(c, ['ɹ'] | ['r']);
+
+    match consonants {
+        [c] => !matches!(c, ['ŋ']),
+        [first, second] => match first {
+            ['p'] | ['b'] | ['k'] | ['g'] | ['ɡ'] | ['f'] => is_liquid(second),
+            ['t'] | ['d'] | ['θ'] | ['ʃ'] => is_rhotic(second),
+            ['s'] => matches!(
+                second,
+                ['p'] | ['t'] | ['k'] | ['m'] | ['n'] | ['l'] | ['f']
+            ),
+            _ => false,
+        },
+        [['s'], stop, liquid] => match stop {
+            ['p'] | ['k'] => is_liquid(liquid),
+            ['t'] => is_rhotic(liquid),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Whether the nucleus is a single short vowel.
+fn is_short(nucleus: &[char]) -> bool {
+    let vowels = nucleus
+        .iter()
+        .skip_while(|&&c| matches!(c, 'j' | 'w'))
+        .collect::<Vec<_>>();
+    matches!(
+        vowels[..],
+        [&'ɪ']
+            | [&'ɛ']
+            | [&'æ']
+            | [&'ʌ']
+            | [&'ʊ']
+            | [&'ɒ']
+            | [&'ə']
+            | [&'ɑ']
+            | [&'e']
+            | [&'a']
+            | [&'ɐ']
+    )
+}
+
+/// Splits the pronunciation into syllables and determines the role of each
+/// symbol. Explicit syllable boundaries, such as stress marks, are respected.
+/// Otherwise the consonants between two vowels start the next syllable, as
+/// long as English allows them to, which is known as the maximal onset
+/// principle.
+pub fn syllabify(chars: &[char]) -> Vec<Role> {
+    let mut roles = vec![Role::Other; chars.len()];
+    let units = units(chars);
+
+    for word in units.split(|u| u.kind == Kind::WordBoundary) {
+        let nuclei = word
+            .iter()
+            .enumerate()
+            .filter(|(_, u)| u.kind == Kind::Vowel)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let (first_nucleus, last_nucleus) = match (nuclei.first(), nuclei.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                for unit in word.iter().filter(|u| u.kind == Kind::Consonant) {
+                    for role in &mut roles[unit.start..unit.start + unit.len] {
+                        *role = Role::Onset;
+                    }
+                }
+                continue;
+            }
+        };
+
+        let mut index = 0;
+        while index < word.len() {
+            let unit = &word[index];
+            if unit.kind == Kind::Vowel {
+                for role in &mut roles[unit.start..unit.start + unit.len] {
+                    *role = Role::Nucleus;
+                }
+            }
+            if unit.kind != Kind::Consonant {
+                index += 1;
+                continue;
+            }
+
+            // Collect the whole cluster of consonants, along with any marks in
+            // between.
+            let end = word[index..]
+                .iter()
+                .position(|u| !matches!(u.kind, Kind::Consonant | Kind::Mark))
+                .map_or(word.len(), |len| index + len);
+            let cluster = &word[index..end];
+            let consonants = cluster
+                .iter()
+                .filter(|u| u.kind == Kind::Consonant)
+                .collect::<Vec<_>>();
+
+            let onset_start = if index < first_nucleus {
+                0
+            } else if index > last_nucleus {
+                consonants.len()
+            } else if let Some(mark) = cluster.iter().rposition(|u| u.kind == Kind::Mark) {
+                cluster[..mark]
+                    .iter()
+                    .filter(|u| u.kind == Kind::Consonant)
+                    .count()
+            } else {
+                let symbols = consonants
+                    .iter()
+                    .map(|u| &chars[u.start..u.start + u.len])
+                    .collect::<Vec<_>>();
+                (0..symbols.len())
+                    .find(|&i| is_onset(&symbols[i..]))
+                    .unwrap_or(symbols.len())
+            };
+
+            let nucleus = word[..index]
+                .iter()
+                .rev()
+                .skip_while(|u| u.kind == Kind::Mark)
+                .take_while(|u| u.kind == Kind::Vowel)
+                .collect::<Vec<_>>();
+            let nucleus = nucleus
+                .iter()
+                .rev()
+                .flat_map(|u| &chars[u.start..u.start + u.len])
+                .copied()
+                .collect::<Vec<_>>();
+            let short_vowel = is_short(&nucleus);
+
+            for (i, unit) in consonants.iter().enumerate() {
+                let role = if i < onset_start {
+                    Role::Coda {
+                        first: i == 0,
+                        short_vowel,
+                    }
+                } else {
+                    Role::Onset
+                };
+                for r in &mut roles[unit.start..unit.start + unit.len] {
+                    *r = role;
+                }
+            }
+
+            index = end;
+        }
+    }
+
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The roles of the symbols, written as `O`nset, `N`ucleus, `C`oda and
+    /// `-` for everything else.
+    fn roles(ipa: &str) -> String {
+        let chars = ipa.chars().collect::<Vec<_>>();
+        syllabify(&chars)
+            .iter()
+            .map(|role| match role {
+                Role::Onset => 'O',
+                Role::Nucleus => 'N',
+                Role::Coda { .. } => 'C',
+                Role::Other => '-',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn maximizes_legal_onsets() {
+        // ɛk.stɹə, as stɹ can start a syllable but kstɹ can't.
+        assert_eq!(roles("ɛkstɹə"), "NCOOON");
+        // æt.ləs, as tl can't start a syllable.
+        assert_eq!(roles("ætləs"), "NCONC");
+    }
+
+    #[test]
+    fn respects_stress_marks() {
+        assert_eq!(roles("ɪkˈspɛnd"), "NC-OONCC");
+    }
+
+    #[test]
+    fn handles_lone_vowels_and_final_clusters() {
+        assert_eq!(roles("ə"), "N");
+        assert_eq!(roles("tɛkst"), "ONCCC");
+        assert_eq!(roles("aɪ|tɛkst"), "NN-ONCCC");
+    }
+
+    #[test]
+    fn knows_the_length_of_the_vowel_before_a_coda() {
+        let chars = "kæt".chars().collect::<Vec<_>>();
+        assert_eq!(
+            syllabify(&chars)[2],
+            Role::Coda {
+                first: true,
+                short_vowel: true,
+            }
+        );
+        let chars = "kiːt".chars().collect::<Vec<_>>();
+        assert_eq!(
+            syllabify(&chars)[3],
+            Role::Coda {
+                first: true,
+                short_vowel: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{ensure, Result};
+use unicase::UniCase;
+
+use crate::pronunciation_source::{Pronunciation, PronunciationSource};
+
+#[derive(Clone)]
+struct Entry {
+    word: String,
+    ipa: Option<String>,
+    hangul: Option<String>,
+}
+
+/// Pronunciations provided by the user, for words that are missing from the
+/// other sources or that they pronounce wrongly. Each line consists of a
+/// word and its pronunciation in IPA, separated by a tab or at least two
+/// spaces. A third column can override the 한글 the word is transcribed
+/// into. Either may be left empty. Empty lines and lines starting with `#`
+/// are ignored. The words apply to any language.
+///
+/// The frontends keep the corrections the user makes in one, so that they
+/// can be exported as a file again, which is what the `Display`
+/// implementation produces.
+#[derive(Clone, Default)]
+pub struct UserDictionary {
+    map: HashMap<UniCase<String>, Entry>,
+}
+
+/// The transcription of a text that a correction is made to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Field {
+    Pronunciation,
+    Hangul,
+}
+
+/// A correction that is still being typed. It is only remembered once it's
+/// committed with [`UserDictionary::commit`], such as when the user presses
+/// Enter, so that a half-typed pronunciation doesn't end up in the
+/// dictionary.
+#[derive(Clone, Debug)]
+pub struct PendingCorrection {
+    field: Field,
+    text: String,
+    original: String,
+    corrected: String,
+}
+
+impl PendingCorrection {
+    /// Starts correcting the transcription of the text, which is the original
+    /// pronunciation or 한글 of its words.
+    pub fn new(field: Field, text: &str, original: &str) -> Self {
+        Self {
+            field,
+            text: text.to_owned(),
+            original: original.to_owned(),
+            corrected: original.to_owned(),
+        }
+    }
+
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn corrected(&self) -> &str {
+        &self.corrected
+    }
+
+    pub fn edit(&mut self, corrected: &str) {
+        self.corrected.clear();
+        self.corrected.push_str(corrected);
+    }
+
+    /// Records an edit of the field the way the frontends do on every
+    /// keystroke. The pending correction keeps the original it started
+    /// from, unless it's of a different field and is replaced.
+    pub fn record(
+        pending: &mut Option<Self>,
+        field: Field,
+        text: &str,
+        original: &str,
+        corrected: &str,
+    ) {
+        match pending {
+            Some(correction) if correction.field == field => correction.edit(corrected),
+            pending => {
+                let mut correction = Self::new(field, text, original);
+                correction.edit(corrected);
+                *pending = Some(correction);
+            }
+        }
+    }
+}
+
+fn non_empty(field: Option<&str>) -> Option<String> {
+    field
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+impl UserDictionary {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn parse(txt: &str) -> Result<Self> {
+        let mut dictionary = Self::new();
+        dictionary.import(txt)?;
+        Ok(dictionary)
+    }
+
+    /// Adds the words of another user dictionary file, replacing the ones
+    /// that are already known.
+    pub fn import(&mut self, txt: &str) -> Result<()> {
+        for (index, line) in txt.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields = if line.contains('\t') {
+                line.split('\t').collect::<Vec<_>>()
+            } else {
+                line.split("  ").filter(|f| !f.trim().is_empty()).collect()
+            };
+
+            let word = fields[0].trim();
+            let ipa = non_empty(fields.get(1).copied());
+            let hangul = non_empty(fields.get(2).copied());
+            ensure!(
+                ipa.is_some() || hangul.is_some(),
+                "Line {} of the user dictionary has no pronunciation.",
+                index + 1
+            );
+
+            let entry = self.entry(word);
+            if ipa.is_some() {
+                entry.ipa = ipa;
+            }
+            if hangul.is_some() {
+                entry.hangul = hangul;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry(&mut self, word: &str) -> &mut Entry {
+        self.map
+            .entry(UniCase::new(word.to_owned()))
+            .or_insert_with(|| Entry {
+                word: word.to_owned(),
+                ipa: None,
+                hangul: None,
+            })
+    }
+
+    pub fn look_up(&self, word: &str) -> Option<Pronunciation> {
+        let entry = self.map.get(&UniCase::new(word.to_owned()))?;
+        Some(Pronunciation {
+            word: entry.word.clone(),
+            ipa: entry.ipa.clone()?,
+            part_of_speech: None,
+        })
+    }
+
+    /// The 한글 the word is transcribed into instead of converting its
+    /// pronunciation.
+    pub fn hangul(&self, word: &str) -> Option<&str> {
+        self.map
+            .get(&UniCase::new(word.to_owned()))?
+            .hangul
+            .as_deref()
+    }
+
+    pub fn set_pronunciation(&mut self, word: &str, ipa: &str) {
+        self.entry(word).ipa = Some(ipa.to_owned());
+    }
+
+    pub fn set_hangul(&mut self, word: &str, hangul: &str) {
+        self.entry(word).hangul = Some(hangul.to_owned());
+    }
+
+    /// Forgets everything about the word.
+    pub fn remove(&mut self, word: &str) {
+        self.map.remove(&UniCase::new(word.to_owned()));
+    }
+
+    /// Remembers the corrections the user made to the pronunciations of the
+    /// words in the text. The pronunciations are separated by whitespace,
+    /// like the words. Only the ones that differ from the original ones are
+    /// kept. Returns `false` if the number of pronunciations doesn't match
+    /// the number of words.
+    pub fn correct_pronunciations(&mut self, text: &str, original: &str, corrected: &str) -> bool {
+        self.correct(text, original, corrected, Self::set_pronunciation)
+    }
+
+    /// Like [`correct_pronunciations`](Self::correct_pronunciations), but for
+    /// the 한글 of the words.
+    pub fn correct_hangul(&mut self, text: &str, original: &str, corrected: &str) -> bool {
+        self.correct(text, original, corrected, Self::set_hangul)
+    }
+
+    /// Remembers the correction once the user is done typing it. Returns
+    /// `false` like [`correct_pronunciations`](Self::correct_pronunciations)
+    /// if it can't be applied.
+    pub fn commit(&mut self, correction: &PendingCorrection) -> bool {
+        let correct = match correction.field {
+            Field::Pronunciation => Self::correct_pronunciations,
+            Field::Hangul => Self::correct_hangul,
+        };
+        correct(
+            self,
+            &correction.text,
+            &correction.original,
+            &correction.corrected,
+        )
+    }
+
+    fn correct(
+        &mut self,
+        text: &str,
+        original: &str,
+        corrected: &str,
+        set: fn(&mut Self, &str, &str),
+    ) -> bool {
+        let words = text.split_whitespace().collect::<Vec<_>>();
+        let original = original.split_whitespace().collect::<Vec<_>>();
+        let corrected = corrected.split_whitespace().collect::<Vec<_>>();
+        if words.len() != corrected.len() || original.len() != corrected.len() {
+            return false;
+        }
+
+        for ((word, original), corrected) in words.into_iter().zip(original).zip(corrected) {
+            if original != corrected {
+                set(self, word, corrected);
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Display for UserDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries = self.map.values().collect::<Vec<_>>();
+        entries.sort_by_key(|e| UniCase::new(&e.word));
+
+        for entry in entries {
+            write!(
+                f,
+                "{}\t{}",
+                entry.word,
+                entry.ipa.as_deref().unwrap_or_default()
+            )?;
+            if let Some(hangul) = &entry.hangul {
+                write!(f, "\t{}", hangul)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl PronunciationSource for UserDictionary {
+    fn name(&self) -> &str {
+        "user dictionary"
+    }
+
+    fn look_up(&self, word: &str, _language: &str) -> Result<Option<Pronunciation>> {
+        Ok(UserDictionary::look_up(self, word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_remembers_committed_corrections() {
+        let mut dictionary = UserDictionary::new();
+        let mut pending = None;
+
+        // Like the web frontend, the field shows every keystroke, so the
+        // original passed along is the previous edit.
+        let mut shown = "hʌloʊ wɝld".to_owned();
+        for partial in &["hʌloʊ w", "hʌloʊ wɜ", "hʌloʊ wɜːl"] {
+            PendingCorrection::record(
+                &mut pending,
+                Field::Pronunciation,
+                "hello world",
+                &shown,
+                partial,
+            );
+            shown = partial.to_string();
+        }
+
+        // Each partial edit fits the words, so committing it would remember
+        // it.
+        let mut committed_early = UserDictionary::new();
+        assert!(committed_early.commit(pending.as_ref().unwrap()));
+        assert_eq!(committed_early.look_up("world").unwrap().ipa, "wɜːl");
+
+        PendingCorrection::record(
+            &mut pending,
+            Field::Pronunciation,
+            "hello world",
+            &shown,
+            "hʌloʊ wɜːld",
+        );
+        assert!(dictionary.commit(&pending.take().unwrap()));
+        assert_eq!(dictionary.len(), 1);
+        assert_eq!(dictionary.look_up("world").unwrap().ipa, "wɜːld");
+        assert!(dictionary.look_up("hello").is_none());
+    }
+
+    #[test]
+    fn replaces_the_pending_correction_of_another_field() {
+        let mut dictionary = UserDictionary::new();
+        let mut pending = None;
+        PendingCorrection::record(&mut pending, Field::Pronunciation, "hello", "hʌloʊ", "hɛl");
+        PendingCorrection::record(&mut pending, Field::Hangul, "hello", "헐로우", "헬로");
+
+        assert!(dictionary.commit(&pending.take().unwrap()));
+        assert_eq!(dictionary.hangul("hello"), Some("헬로"));
+        assert!(dictionary.look_up("hello").is_none());
+    }
+
+    #[test]
+    fn rejects_corrections_with_a_different_number_of_words() {
+        let mut dictionary = UserDictionary::new();
+        let mut correction = PendingCorrection::new(Field::Hangul, "hello world", "헐로우 월드");
+        correction.edit("헬로월드");
+        assert!(!dictionary.commit(&correction));
+        assert!(dictionary.is_empty());
+    }
+}
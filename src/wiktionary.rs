@@ -0,0 +1,212 @@
+use std::{collections::HashMap, io::BufRead};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use unicase::UniCase;
+
+use crate::{
+    accent::Accent,
+    pronunciation_source::{Pronunciation, PronunciationSource},
+};
+
+#[derive(Deserialize)]
+struct RawEntry {
+    word: String,
+    lang_code: String,
+    pos: Option<String>,
+    #[serde(default)]
+    sounds: Vec<Sound>,
+}
+
+#[derive(Deserialize)]
+struct Sound {
+    ipa: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A pronunciation along with the accents it belongs to.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub pronunciation: Pronunciation,
+    /// The accents or regions the pronunciation is tagged with, such as `UK`
+    /// or `General-American`. Empty if it isn't specific to any of them.
+    pub accents: Vec<String>,
+}
+
+impl Entry {
+    pub fn has_accent(&self, accent: &str) -> bool {
+        self.accents
+            .iter()
+            .any(|a| UniCase::new(&**a) == UniCase::new(accent))
+    }
+}
+
+/// Strips the slashes or brackets around the IPA.
+fn strip_delimiters(ipa: &str) -> &str {
+    let ipa = ipa.trim();
+    ipa.strip_prefix('/')
+        .and_then(|ipa| ipa.strip_suffix('/'))
+        .or_else(|| ipa.strip_prefix('[').and_then(|ipa| ipa.strip_suffix(']')))
+        .unwrap_or(ipa)
+        .trim()
+}
+
+/// Pronunciations extracted from Wiktionary, in any of its languages. The
+/// extract is read in the JSON Lines format provided by kaikki.org, with one
+/// entry per line. Only the IPA of each entry is kept.
+#[derive(Default)]
+pub struct Dictionary {
+    map: HashMap<(String, UniCase<String>), Vec<Entry>>,
+    accent: Option<Accent>,
+}
+
+impl Dictionary {
+    /// Prefers the pronunciations in the accent when used as a
+    /// [`PronunciationSource`]. Pronunciations that aren't marked with any
+    /// accent come next.
+    pub fn with_accent(mut self, accent: Accent) -> Self {
+        self.accent = Some(accent);
+        self
+    }
+
+    pub fn parse(txt: &str) -> Result<Self> {
+        Self::read(txt.as_bytes())
+    }
+
+    /// Reads the extract line by line, so the whole file doesn't need to be
+    /// in memory.
+    pub fn read(reader: impl BufRead) -> Result<Self> {
+        let mut dictionary = Self::default();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.context("Failed reading the Wiktionary extract.")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: RawEntry = serde_json::from_str(&line).with_context(|| {
+                format!("Line {} of the Wiktionary extract is invalid.", index + 1)
+            })?;
+            dictionary.insert(entry);
+        }
+
+        Ok(dictionary)
+    }
+
+    fn insert(&mut self, raw: RawEntry) {
+        let RawEntry {
+            word,
+            lang_code,
+            pos,
+            sounds,
+        } = raw;
+        let mut sounds = sounds
+            .into_iter()
+            .filter_map(|sound| {
+                let ipa = strip_delimiters(sound.ipa.as_deref()?).to_owned();
+                Some((ipa, sound.tags)).filter(|(ipa, _)| !ipa.is_empty())
+            })
+            .peekable();
+        if sounds.peek().is_none() {
+            return;
+        }
+
+        let entries = self
+            .map
+            .entry((lang_code, UniCase::new(word.clone())))
+            .or_default();
+        for (ipa, accents) in sounds {
+            // Different parts of speech often share the same pronunciation.
+            if entries
+                .iter()
+                .any(|e| e.pronunciation.ipa == ipa && e.accents == accents)
+            {
+                continue;
+            }
+            entries.push(Entry {
+                pronunciation: Pronunciation {
+                    word: word.clone(),
+                    ipa,
+                    part_of_speech: pos.clone(),
+                },
+                accents,
+            });
+        }
+    }
+
+    /// Returns all the pronunciations of the word in the language, given as
+    /// an ISO 639-1 code.
+    pub fn look_up(&self, word: &str, language: &str) -> &[Entry] {
+        self.map
+            .get(&(language.to_owned(), UniCase::new(word.to_owned())))
+            .map_or(&[], |entries| &**entries)
+    }
+
+    /// Returns the pronunciations of the word that are tagged with the accent,
+    /// such as `UK` or `US`.
+    pub fn look_up_accent<'a>(
+        &'a self,
+        word: &str,
+        language: &str,
+        accent: &'a str,
+    ) -> impl Iterator<Item = &'a Pronunciation> + 'a {
+        self.look_up(word, language)
+            .iter()
+            .filter(move |e| e.has_accent(accent))
+            .map(|e| &e.pronunciation)
+    }
+
+    /// Returns the pronunciations of the word in the order of preference.
+    fn preferred(&self, word: &str, language: &str) -> Vec<&Entry> {
+        let mut entries = self.look_up(word, language).iter().collect::<Vec<_>>();
+        if let Some(accent) = self.accent {
+            let tags = accent.wiktionary_tags();
+            entries.sort_by_key(|e| {
+                if tags.iter().any(|tag| e.has_accent(tag)) {
+                    0
+                } else if e.accents.is_empty() {
+                    1
+                } else {
+                    2
+                }
+            });
+        }
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl PronunciationSource for Dictionary {
+    fn name(&self) -> &str {
+        "Wiktionary"
+    }
+
+    fn look_up(&self, word: &str, language: &str) -> Result<Option<Pronunciation>> {
+        Ok(self
+            .preferred(word, language)
+            .first()
+            .map(|e| e.pronunciation.clone()))
+    }
+
+    /// The same pronunciation may be listed for multiple accents, but it's
+    /// only returned once.
+    fn look_up_all(&self, word: &str, language: &str) -> Result<Vec<Pronunciation>> {
+        let mut pronunciations = Vec::<Pronunciation>::new();
+        for entry in self.preferred(word, language) {
+            if !pronunciations
+                .iter()
+                .any(|p| p.ipa == entry.pronunciation.ipa)
+            {
+                pronunciations.push(entry.pronunciation.clone());
+            }
+        }
+        Ok(pronunciations)
+    }
+}
@@ -1,6 +1,15 @@
-use anyhow::{Context, Result};
-use reqwest::Url;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures::{stream, StreamExt};
+use reqwest::{header::RETRY_AFTER, StatusCode, Url};
 use serde::{de::DeserializeOwned, Deserialize};
+use tokio::sync::Semaphore;
+
+use crate::pronunciation_source::{self, PronunciationSource};
 
 #[derive(Deserialize)]
 #[serde(untagged)]
@@ -14,6 +23,25 @@ struct ErrorResponse {
     message: String,
 }
 
+/// The error for a response that wasn't successful, along with the message
+/// of the API if the body has one.
+fn status_error(status: StatusCode, body: &str) -> anyhow::Error {
+    let context = format!("The dictionary API responded with {}.", status);
+    match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(e) => anyhow!("{}", e.message).context(context),
+        Err(_) => anyhow!(context),
+    }
+}
+
+impl<T> ResponseResult<T> {
+    fn into_result(self) -> Result<T> {
+        match self {
+            ResponseResult::Ok(t) => Ok(t),
+            ResponseResult::Err(e) => anyhow::bail!("{}", e.message),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct SearchResponse {
     results: Vec<SearchResult>,
@@ -27,6 +55,14 @@ struct SearchResult {
 #[derive(Deserialize)]
 struct Entry {
     headword: HeadWordList,
+    #[serde(default)]
+    senses: Vec<Sense>,
+}
+
+#[derive(Deserialize)]
+struct Sense {
+    #[serde(default)]
+    definition: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -39,7 +75,10 @@ enum HeadWordList {
 #[derive(Deserialize)]
 struct HeadWord {
     text: String,
-    pronunciation: Pronunciation,
+    #[serde(default)]
+    pronunciation: Option<Pronunciation>,
+    #[serde(default)]
+    pos: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -47,84 +86,520 @@ struct Pronunciation {
     value: String,
 }
 
+#[derive(Debug)]
 pub struct Word {
     pub word: String,
     pub pronunciation: String,
+    /// The part of speech, such as `noun`, if the dictionary specifies it.
+    pub part_of_speech: Option<String>,
+    /// The definitions of the senses of the dictionary entry the word is
+    /// part of.
+    pub definitions: Vec<String>,
 }
 
-pub struct Client {
-    inner: reqwest::blocking::Client,
+impl Entry {
+    /// Lists every headword of the entry with each of its pronunciations.
+    /// Headwords without a pronunciation are left out.
+    fn into_words(self) -> Vec<Word> {
+        let headwords = match self.headword {
+            HeadWordList::Single(s) => vec![s],
+            HeadWordList::Multiple(m) => m,
+        };
+        let definitions = self
+            .senses
+            .into_iter()
+            .filter_map(|sense| sense.definition)
+            .collect::<Vec<_>>();
+
+        let mut words = Vec::new();
+        for headword in headwords {
+            let pronunciation = match headword.pronunciation {
+                Some(pronunciation) => pronunciation.value,
+                None => continue,
+            };
+            for variant in pronunciation.split(',').map(str::trim) {
+                if variant.is_empty() {
+                    continue;
+                }
+                words.push(Word {
+                    word: headword.text.clone(),
+                    pronunciation: variant.to_owned(),
+                    part_of_speech: headword.pos.clone(),
+                    definitions: definitions.clone(),
+                });
+            }
+        }
+        words
+    }
+}
+
+/// Adds the words to the list, unless they are already part of it.
+fn push_unique(words: &mut Vec<Word>, new_words: Vec<Word>) {
+    for word in new_words {
+        let is_duplicate = words.iter().any(|w| {
+            w.word == word.word
+                && w.pronunciation == word.pronunciation
+                && w.part_of_speech == word.part_of_speech
+        });
+        if !is_duplicate {
+            words.push(word);
+        }
+    }
+}
+
+struct Endpoints {
+    base_url: Url,
+}
+
+impl Endpoints {
+    fn join(&self, path: &str) -> Result<Url> {
+        self.base_url
+            .join(path)
+            .with_context(|| format!("Failed building the URL for `{}`.", path))
+    }
+
+    fn search(&self, word: &str, language: &str) -> Result<Url> {
+        let mut url = self.join("search")?;
+        url.query_pairs_mut()
+            .append_pair("language", language)
+            .append_pair("text", word);
+        Ok(url)
+    }
+
+    fn entry(&self, id: &str) -> Result<Url> {
+        self.join("entries/")?
+            .join(id)
+            .with_context(|| format!("Failed building the URL for the entry `{}`.", id))
+    }
+}
+
+/// The Lexicala dictionary API.
+pub const DEFAULT_BASE_URL: &str = "https://dictapi.lexicala.com/";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+const DEFAULT_REQUESTS_PER_SECOND: u32 = 10;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// How long the async client waits before retrying a request for the first
+/// time. The wait doubles with each further retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The longest the async client waits before retrying, even if the API asks
+/// for a longer wait.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+pub struct ClientBuilder {
+    base_url: String,
+    timeout: Duration,
+    user_agent: String,
+    max_concurrency: usize,
+    requests_per_second: u32,
+    max_retries: u32,
     user: String,
     password: String,
 }
 
-impl Client {
+impl ClientBuilder {
     pub fn new(user: String, password: String) -> Self {
         Self {
-            inner: reqwest::blocking::Client::new(),
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            requests_per_second: DEFAULT_REQUESTS_PER_SECOND,
+            max_retries: DEFAULT_MAX_RETRIES,
             user,
             password,
         }
     }
 
+    /// Sets the URL the dictionary API is reached at, such as a proxy or a
+    /// mirror. Defaults to [`DEFAULT_BASE_URL`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets how long a request may take in total. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets how many requests the async client sends at the same time.
+    /// Defaults to 4.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Sets how many requests the async client starts per second at most,
+    /// to stay within the limits of the API. 0 means no limit. Defaults to
+    /// 10.
+    pub fn requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = requests_per_second;
+        self
+    }
+
+    /// Sets how often the async client retries a request that failed due to
+    /// a timeout, a connection error, the rate limit or a server error.
+    /// Defaults to 3.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    fn endpoints(&self) -> Result<Endpoints> {
+        let mut base_url = self.base_url.clone();
+        // Without the trailing slash, joining would replace the last segment
+        // of the path.
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        let base_url = Url::parse(&base_url).context("The base URL is invalid.")?;
+        Ok(Endpoints { base_url })
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let endpoints = self.endpoints()?;
+
+        let inner = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(self.user_agent)
+            .build()
+            .context("Failed creating the HTTP client.")?;
+
+        Ok(Client {
+            inner,
+            endpoints,
+            user: self.user,
+            password: self.password,
+        })
+    }
+
+    /// Builds a client that doesn't block. It has to be used within a Tokio
+    /// runtime.
+    pub fn build_async(self) -> Result<AsyncClient> {
+        let endpoints = self.endpoints()?;
+
+        let inner = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(self.user_agent)
+            .build()
+            .context("Failed creating the HTTP client.")?;
+
+        let interval = match self.requests_per_second {
+            0 => Duration::from_secs(0),
+            n => Duration::from_secs(1) / n,
+        };
+
+        Ok(AsyncClient {
+            inner,
+            endpoints,
+            user: self.user,
+            password: self.password,
+            permits: Semaphore::new(self.max_concurrency),
+            max_concurrency: self.max_concurrency,
+            rate_limiter: RateLimiter {
+                interval,
+                next: Mutex::new(Instant::now()),
+            },
+            max_retries: self.max_retries,
+        })
+    }
+}
+
+pub struct Client {
+    inner: reqwest::blocking::Client,
+    endpoints: Endpoints,
+    user: String,
+    password: String,
+}
+
+impl Client {
+    /// Creates a client for the Lexicala dictionary API with the default
+    /// settings.
+    pub fn new(user: String, password: String) -> Result<Self> {
+        ClientBuilder::new(user, password).build()
+    }
+
+    pub fn builder(user: String, password: String) -> ClientBuilder {
+        ClientBuilder::new(user, password)
+    }
+
     fn call_api<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
-        let result: ResponseResult<T> = self
+        let response = self
             .inner
             .get(url)
             .basic_auth(&self.user, Some(&self.password))
             .send()
-            .context("Failed accessing the dictionary API.")?
+            .context("Failed accessing the dictionary API.")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(status, &response.text().unwrap_or_default()));
+        }
+
+        let result: ResponseResult<T> = response
             .json()
             .context("Failed parsing the response from the dictionary API.")?;
 
-        match result {
-            ResponseResult::Ok(t) => Ok(t),
-            ResponseResult::Err(e) => anyhow::bail!("{}", e.message),
-        }
+        result.into_result()
     }
 
+    /// Looks up the first pronunciation of the word.
     pub fn lookup(&self, word: &str, language: &str) -> Result<Word> {
+        let results = self.search(word, language)?;
+        let id = &results
+            .first()
+            .context("The dictionary does not contain the word.")?
+            .id;
+
+        self.entry(id)?
+            .into_words()
+            .into_iter()
+            .next()
+            .context("The dictionary does not know the word's pronunciation.")
+    }
+
+    /// Looks up every pronunciation of the word: all the variants of all the
+    /// headwords of all the dictionary entries that match it. This needs a
+    /// request per entry. The list is empty if the dictionary doesn't
+    /// contain the word.
+    pub fn lookup_all(&self, word: &str, language: &str) -> Result<Vec<Word>> {
+        let mut words = Vec::new();
+        for result in self.search(word, language)? {
+            push_unique(&mut words, self.entry(&result.id)?.into_words());
+        }
+        Ok(words)
+    }
+
+    fn search(&self, word: &str, language: &str) -> Result<Vec<SearchResult>> {
         let SearchResponse { results } = self
-            .call_api(
-                Url::parse_with_params(
-                    "https://dictapi.lexicala.com/search",
-                    &[("language", language), ("text", word)],
-                )
-                .unwrap(),
-            )
+            .call_api(self.endpoints.search(word, language)?)
             .context("Failed searching the word via the dictionary API.")?;
 
-        let entry: Entry = self
-            .call_api(
-                Url::parse("https://dictapi.lexicala.com/entries/")
-                    .unwrap()
-                    .join(
-                        &results
-                            .get(0)
-                            .context("The dictionary does not contain the word.")?
-                            .id,
-                    )
-                    .unwrap(),
-            )
-            .context("Failed looking up the word's pronunciation via the dictionary API.")?;
-
-        let headword = match entry.headword {
-            HeadWordList::Single(s) => s,
-            HeadWordList::Multiple(m) => m
-                .into_iter()
-                .next()
-                .context("The word contains a list of words that is empty.")?,
+        Ok(results)
+    }
+
+    fn entry(&self, id: &str) -> Result<Entry> {
+        self.call_api(self.endpoints.entry(id)?)
+            .context("Failed looking up the word's pronunciation via the dictionary API.")
+    }
+}
+
+/// Spaces out the starts of requests evenly.
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    async fn wait(&self) {
+        let delay = {
+            let mut next = match self.next.lock() {
+                Ok(next) => next,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot - now
         };
+        if delay > Duration::from_secs(0) {
+            tokio::time::delay_for(delay).await;
+        }
+    }
+}
 
-        Ok(Word {
-            word: headword.text,
-            pronunciation: headword
-                .pronunciation
-                .value
-                .splitn(2, ',')
-                .next()
-                .unwrap()
-                .to_owned(),
-        })
+/// Whether the request may succeed when it's sent again later.
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// A client for the dictionary API that doesn't block, for looking up many
+/// words concurrently. It limits how many requests are sent at the same time
+/// and how many are started per second, and retries requests that failed
+/// temporarily.
+pub struct AsyncClient {
+    inner: reqwest::Client,
+    endpoints: Endpoints,
+    user: String,
+    password: String,
+    permits: Semaphore,
+    max_concurrency: usize,
+    rate_limiter: RateLimiter,
+    max_retries: u32,
+}
+
+impl AsyncClient {
+    async fn call_api<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
+        let _permit = self.permits.acquire().await;
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retries = 0;
+
+        loop {
+            self.rate_limiter.wait().await;
+
+            let response = self
+                .inner
+                .get(url.clone())
+                .basic_auth(&self.user, Some(&self.password))
+                .send()
+                .await;
+
+            let retry_delay = match &response {
+                Ok(response) if is_transient(response.status()) => Some(
+                    // The API may tell us how long to wait.
+                    response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok()?.parse().ok())
+                        .map_or(backoff, Duration::from_secs)
+                        .min(MAX_RETRY_DELAY),
+                ),
+                Err(e) if e.is_timeout() || e.is_connect() => Some(backoff),
+                _ => None,
+            };
+
+            if let Some(delay) = retry_delay {
+                if retries < self.max_retries {
+                    retries += 1;
+                    backoff *= 2;
+                    tokio::time::delay_for(delay).await;
+                    continue;
+                }
+            }
+
+            let response = response.context("Failed accessing the dictionary API.")?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(status_error(status, &body));
+            }
+
+            let result: ResponseResult<T> = response
+                .json()
+                .await
+                .context("Failed parsing the response from the dictionary API.")?;
+
+            return result.into_result();
+        }
+    }
+
+    /// Looks up the first pronunciation of the word.
+    pub async fn lookup(&self, word: &str, language: &str) -> Result<Word> {
+        let results = self.search(word, language).await?;
+        let id = &results
+            .first()
+            .context("The dictionary does not contain the word.")?
+            .id;
+
+        self.entry(id)
+            .await?
+            .into_words()
+            .into_iter()
+            .next()
+            .context("The dictionary does not know the word's pronunciation.")
+    }
+
+    /// Looks up every pronunciation of the word, like
+    /// [`Client::lookup_all`].
+    pub async fn lookup_all(&self, word: &str, language: &str) -> Result<Vec<Word>> {
+        let mut words = Vec::new();
+        for result in self.search(word, language).await? {
+            push_unique(&mut words, self.entry(&result.id).await?.into_words());
+        }
+        Ok(words)
+    }
+
+    /// Looks up every pronunciation of each of the words concurrently. The
+    /// results are in the same order as the words.
+    pub async fn lookup_batch(
+        &self,
+        words: Vec<String>,
+        language: &str,
+    ) -> Vec<(String, Result<Vec<Word>>)> {
+        stream::iter(words)
+            .map(|word| async move {
+                let result = self.lookup_all(&word, language).await;
+                (word, result)
+            })
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    async fn search(&self, word: &str, language: &str) -> Result<Vec<SearchResult>> {
+        let SearchResponse { results } = self
+            .call_api(self.endpoints.search(word, language)?)
+            .await
+            .context("Failed searching the word via the dictionary API.")?;
+
+        Ok(results)
+    }
+
+    async fn entry(&self, id: &str) -> Result<Entry> {
+        self.call_api(self.endpoints.entry(id)?)
+            .await
+            .context("Failed looking up the word's pronunciation via the dictionary API.")
+    }
+}
+
+impl From<Word> for pronunciation_source::Pronunciation {
+    fn from(word: Word) -> Self {
+        Self {
+            word: word.word,
+            ipa: word.pronunciation,
+            part_of_speech: word.part_of_speech,
+        }
+    }
+}
+
+impl PronunciationSource for Client {
+    fn name(&self) -> &str {
+        "Lexicala"
+    }
+
+    fn look_up(
+        &self,
+        word: &str,
+        language: &str,
+    ) -> Result<Option<pronunciation_source::Pronunciation>> {
+        let results = self.search(word, language)?;
+        let id = match results.first() {
+            Some(result) => &result.id,
+            None => return Ok(None),
+        };
+        Ok(self
+            .entry(id)?
+            .into_words()
+            .into_iter()
+            .next()
+            .map(Into::into))
+    }
+
+    fn look_up_all(
+        &self,
+        word: &str,
+        language: &str,
+    ) -> Result<Vec<pronunciation_source::Pronunciation>> {
+        Ok(self
+            .lookup_all(word, language)?
+            .into_iter()
+            .map(Into::into)
+            .collect())
     }
 }
@@ -0,0 +1,73 @@
+use phonetics_to_hangul::{
+    arpabet,
+    batch::{self, Transcription},
+    document::{self, Document, Format},
+    pronunciation_source::Resolver,
+};
+
+#[test]
+fn round_trips_csv() {
+    let csv = "hello,\"a, b\"\r\n\"say \"\"hi\"\"\",\"two\nlines\"\r\n";
+    let document = Document::parse(Format::Csv, csv).unwrap();
+    assert_eq!(
+        document.texts().collect::<Vec<_>>(),
+        ["hello", "say \"hi\""]
+    );
+
+    let rows = document.transcribe(|text| (text.to_owned(), String::new()));
+    assert_eq!(
+        document::to_csv(&rows),
+        "hello,\"a, b\",hello,\r\n\"say \"\"hi\"\"\",\"two\nlines\",\"say \"\"hi\"\"\",\r\n"
+    );
+}
+
+#[test]
+fn names_the_added_columns_in_the_header() {
+    let csv = "word,meaning\r\nhello,greeting\r\n";
+    let document = Document::parse(Format::Csv, csv).unwrap().with_header(true);
+    assert_eq!(document.texts().collect::<Vec<_>>(), ["hello"]);
+
+    let rows = document.transcribe(|text| (text.to_uppercase(), String::new()));
+    assert_eq!(
+        document::to_csv(&rows),
+        "word,meaning,ipa,hangul\r\nhello,greeting,HELLO,\r\n"
+    );
+}
+
+#[test]
+fn parses_subtitles() {
+    let srt = "1\r\n00:00:01,000 --> 00:00:02,000\r\nHello,\r\nworld!\r\n\r\n\
+               2\r\n00:00:03,000 --> 00:00:04,000\r\nDon't stop.\r\n";
+    let document = Document::parse(Format::Subtitles, srt).unwrap();
+    assert_eq!(
+        document.texts().collect::<Vec<_>>(),
+        ["Hello, world!", "Don't stop."]
+    );
+
+    assert!(Document::parse(Format::Subtitles, "1\nHello\n").is_err());
+}
+
+#[test]
+fn transcribes_texts_word_by_word() {
+    let dictionary = arpabet::Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+    let mut resolver = Resolver::new();
+    resolver.push(dictionary);
+
+    let texts = ["Hello, world!", "hello xyzzyq don't"];
+    let words = batch::words(texts.iter().copied());
+    assert_eq!(words, ["Hello", "world", "xyzzyq", "don't"]);
+
+    let transcriptions = batch::resolve(&resolver, &words, "en")
+        .iter()
+        .map(|outcome| outcome.transcriptions(false, Default::default()).pop())
+        .collect::<Vec<_>>();
+    assert!(transcriptions[2].is_none());
+
+    let transcribe = |word: &str| -> Option<&Transcription> {
+        let index = words.iter().position(|w| w.eq_ignore_ascii_case(word))?;
+        transcriptions[index].as_ref()
+    };
+    let (ipa, hangul) = batch::transcribe_text(texts[1], transcribe);
+    assert_eq!(ipa, "hʌloʊ xyzzyq doʊnt");
+    assert_eq!(hangul, "헐로우 xyzzyq 또운트");
+}
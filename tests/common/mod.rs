@@ -0,0 +1,41 @@
+//! Builder operations shared by the property and snapshot tests.
+
+#![allow(dead_code)]
+
+use proptest::prelude::*;
+
+use phonetics_to_hangul::hangul_builder::{Builder, Consonant, Error, Vowel};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Op {
+    C(Consonant),
+    F(Consonant),
+    Cluster(Consonant, Consonant),
+    V(Vowel),
+    Space,
+}
+
+pub fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => prop::sample::select(&Consonant::ALL[..]).prop_map(Op::C),
+        1 => prop::sample::select(&Consonant::ALL[..]).prop_map(Op::F),
+        1 => (
+            prop::sample::select(&Consonant::ALL[..]),
+            prop::sample::select(&Consonant::ALL[..]),
+        )
+            .prop_map(|(first, second)| Op::Cluster(first, second)),
+        3 => prop::sample::select(&Vowel::ALL[..]).prop_map(Op::V),
+        1 => Just(Op::Space),
+    ]
+}
+
+pub fn apply(builder: &mut Builder, op: Op) -> Result<(), Error> {
+    match op {
+        Op::C(cons) => builder.push_consonant(cons),
+        Op::F(cons) => return builder.push_final_consonant(cons),
+        Op::Cluster(first, second) => return builder.push_final_cluster(first, second),
+        Op::V(vowel) => builder.push_vowel(vowel),
+        Op::Space => builder.push_space(),
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use phonetics_to_hangul::{
+    arpabet::{self, Dictionary},
+    evaluation::{self, Report},
+    ipa_to_hangul::Options,
+};
+
+// Raise these whenever a change improves the transcription, so that it
+// can't silently get worse again.
+const MIN_ACCURACY: f32 = 0.11;
+const MAX_MEAN_DISTANCE: f32 = 1.8;
+
+fn evaluate_loanwords() -> Report {
+    let corpus = evaluation::parse_corpus(evaluation::LOANWORDS).unwrap();
+    let dictionary = Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+    evaluation::evaluate(&corpus, Options::default(), |word| {
+        Some(dictionary.look_up(word)?.collect())
+    })
+}
+
+#[test]
+fn does_not_regress_on_loanwords() {
+    let report = evaluate_loanwords();
+    assert!(report.missing.is_empty(), "{}", report);
+    assert!(report.accuracy() >= MIN_ACCURACY, "{}", report);
+    assert!(report.mean_distance() <= MAX_MEAN_DISTANCE, "{}", report);
+}
@@ -0,0 +1,90 @@
+use proptest::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+mod common;
+
+use phonetics_to_hangul::{
+    hangul_builder::{Builder, Consonant, Error, Vowel},
+    ipa_to_hangul,
+};
+
+use common::{apply, op, Op};
+
+fn is_syllable(c: char) -> bool {
+    ('\u{AC00}'..='\u{D7A3}').contains(&c)
+}
+
+proptest! {
+    #[test]
+    fn builds_valid_syllables(ops in prop::collection::vec(op(), 0..64)) {
+        let mut builder = Builder::new();
+        for &op in &ops {
+            let _ = apply(&mut builder, op);
+        }
+        let text = builder.finish().collect::<String>();
+
+        prop_assert!(text.chars().all(|c| is_syllable(c) || c == ' '), "{:?}", text);
+        prop_assert_eq!(text.nfc().collect::<String>(), text.clone());
+
+        let vowels = ops.iter().filter(|op| matches!(op, Op::V(_))).count();
+        let spaces = ops.iter().filter(|op| matches!(op, Op::Space)).count();
+        prop_assert!(text.chars().filter(|&c| is_syllable(c)).count() >= vowels);
+        prop_assert_eq!(text.chars().filter(|&c| c == ' ').count(), spaces);
+    }
+
+    #[test]
+    fn restarts_cleanly(first in prop::collection::vec(op(), 0..32), vowel in prop::sample::select(&Vowel::ALL[..])) {
+        let mut builder = Builder::new();
+        for op in first {
+            let _ = apply(&mut builder, op);
+        }
+        builder.restart();
+        builder.push_vowel(vowel);
+
+        let mut fresh = Builder::new();
+        fresh.push_vowel(vowel);
+        prop_assert_eq!(builder.finish().collect::<String>(), fresh.finish().collect::<String>());
+    }
+
+    #[test]
+    fn converts_any_ipa_to_valid_syllables(ipa in "[abdefhijklmnoprstuvwyzæɐɑɒɔəɛɜɝɪʊʌθðʃʒŋɹɡˈˌː|]{0,32}") {
+        let mut builder = Builder::new();
+        let text = ipa_to_hangul::convert(&mut builder, ipa.chars()).collect::<String>();
+        prop_assert!(text.chars().all(|c| is_syllable(c) || c == ' '), "{:?}", text);
+    }
+}
+
+#[test]
+fn builds_final_clusters() {
+    let mut builder = Builder::new();
+    builder.push_consonant(Consonant::D);
+    builder.push_vowel(Vowel::A);
+    builder
+        .push_final_cluster(Consonant::L, Consonant::G)
+        .unwrap();
+    builder.push_space();
+    builder.push_vowel(Vowel::A);
+    builder
+        .push_final_cluster(Consonant::N, Consonant::J)
+        .unwrap();
+    assert_eq!(builder.finish().collect::<String>(), "닭 앉");
+}
+
+#[test]
+fn rejects_invalid_final_clusters() {
+    let mut builder = Builder::new();
+    builder.push_consonant(Consonant::D);
+    builder.push_vowel(Vowel::A);
+    for &(first, second) in &[
+        (Consonant::G, Consonant::L),
+        (Consonant::L, Consonant::L),
+        (Consonant::M, Consonant::B),
+        (Consonant::S, Consonant::G),
+    ] {
+        assert_eq!(
+            builder.push_final_cluster(first, second),
+            Err(Error::InvalidFinalCluster(first, second))
+        );
+    }
+    assert_eq!(builder.finish().collect::<String>(), "다");
+}
@@ -0,0 +1,208 @@
+//! Golden file tests for the conversion pipeline. When a snapshot changes,
+//! the new output is written next to it with a `.new` extension and the test
+//! fails. Once the changes are reviewed, run the tests with
+//! `UPDATE_SNAPSHOTS=1` to accept them.
+
+mod common;
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+use phonetics_to_hangul::{
+    accent::Accent,
+    arpabet::{self, Dictionary},
+    hangul_builder::{Builder, Consonant, Vowel},
+    ipa_to_hangul::{self, Options},
+};
+
+use common::{apply, Op};
+
+/// How many of the differing lines are shown when a snapshot changes.
+const MAX_SHOWN_CHANGES: usize = 40;
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.snap", name));
+    let new_path = path.with_extension("snap.new");
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        let _ = fs::remove_file(&new_path);
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    if expected == actual {
+        let _ = fs::remove_file(&new_path);
+        return;
+    }
+    fs::write(&new_path, actual).unwrap();
+
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut changes = Vec::new();
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e == a => {}
+            (e, a) => changes.push(format!(
+                "- {}\n+ {}",
+                e.unwrap_or_default(),
+                a.unwrap_or_default()
+            )),
+        }
+    }
+
+    panic!(
+        "The snapshot `{}` changed in {} lines:\n{}\n\nReview {} and run the tests \
+        with UPDATE_SNAPSHOTS=1 to accept the changes.",
+        name,
+        changes.len(),
+        changes
+            .iter()
+            .take(MAX_SHOWN_CHANGES)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n"),
+        new_path.display(),
+    );
+}
+
+/// Every 100th word of CMUdict, leaving out alternative pronunciations and
+/// entries for punctuation.
+fn words() -> Vec<&'static str> {
+    arpabet::CMUDICT_07B
+        .lines()
+        .filter(|l| !l.starts_with(";;;"))
+        .filter_map(|l| l.split("  ").next())
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_alphabetic()) && !w.contains('('))
+        .step_by(100)
+        .collect()
+}
+
+#[test]
+fn dictionary() {
+    let dictionary = Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+    let mut snapshot = String::new();
+
+    for word in words() {
+        let ipa = dictionary.look_up(word).unwrap().collect::<String>();
+        writeln!(snapshot, "{}\t{}", word, ipa).unwrap();
+    }
+
+    assert_snapshot("dictionary", &snapshot);
+}
+
+#[test]
+fn ipa_to_hangul() {
+    let dictionary = Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+    let mut builder = Builder::new();
+    let mut snapshot = String::new();
+
+    for word in words() {
+        let ipa = dictionary.look_up(word).unwrap().collect::<String>();
+        let steps = ipa_to_hangul::trace(&mut builder, ipa.chars(), Options::default())
+            .iter()
+            .map(|step| format!("{}:{}", step.ipa, step.jamo))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let hangul = builder.finish().collect::<String>();
+        writeln!(snapshot, "{}\t{}\t{}", ipa, steps, hangul).unwrap();
+    }
+
+    assert_snapshot("ipa_to_hangul", &snapshot);
+}
+
+/// Words whose British pronunciation differs from the American one.
+const BRITISH_WORDS: &[&str] = &[
+    "boat", "go", "home", "car", "water", "bird", "better", "hair", "near", "tour", "hot",
+];
+
+#[test]
+fn british_ipa_to_hangul() {
+    let dictionary = Dictionary::parse(arpabet::CMUDICT_07B).unwrap();
+    let options = Options {
+        accent: Accent::British,
+    };
+    let mut snapshot = String::new();
+
+    for &word in BRITISH_WORDS {
+        let ipa = dictionary.look_up_accent(word, Accent::British).unwrap();
+        let hangul = ipa_to_hangul::convert_with(&mut Builder::new(), ipa.chars(), options)
+            .collect::<String>();
+        writeln!(snapshot, "{}\t{}\t{}", word, ipa, hangul).unwrap();
+    }
+
+    assert_snapshot("british_ipa_to_hangul", &snapshot);
+}
+
+#[test]
+fn hangul_builder() {
+    use Op::*;
+
+    let mut cases = Vec::new();
+    for &c in &Consonant::ALL {
+        cases.push(vec![C(c)]);
+        cases.push(vec![C(c), V(Vowel::A)]);
+        cases.push(vec![V(Vowel::A), C(c)]);
+        cases.push(vec![V(Vowel::A), C(c), V(Vowel::A)]);
+        cases.push(vec![V(Vowel::A), F(c)]);
+        cases.push(vec![V(Vowel::A), F(c), V(Vowel::A)]);
+    }
+    for &v in &Vowel::ALL {
+        cases.push(vec![V(v)]);
+        cases.push(vec![C(Consonant::G), V(v)]);
+    }
+    let clusters = [
+        Consonant::G,
+        Consonant::N,
+        Consonant::L,
+        Consonant::M,
+        Consonant::P,
+        Consonant::S,
+        Consonant::K,
+        Consonant::T,
+    ];
+    for &first in &clusters {
+        for &second in &clusters {
+            cases.push(vec![V(Vowel::A), C(first), C(second), V(Vowel::A)]);
+            cases.push(vec![V(Vowel::A), C(first), C(second)]);
+        }
+    }
+    cases.push(vec![C(Consonant::S), C(Consonant::T), V(Vowel::A)]);
+    cases.push(vec![
+        C(Consonant::G),
+        V(Vowel::A),
+        Space,
+        C(Consonant::N),
+        V(Vowel::A),
+    ]);
+    cases.push(vec![C(Consonant::S), Space, V(Vowel::A)]);
+
+    let mut builder = Builder::new();
+    let mut snapshot = String::new();
+    for ops in cases {
+        builder.restart();
+        for &op in &ops {
+            if let (Err(_), F(c)) = (apply(&mut builder, op), op) {
+                write!(snapshot, "(invalid final {:?}) ", c).unwrap();
+            }
+        }
+        let ops = ops
+            .iter()
+            .map(|op| format!("{:?}", op))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            snapshot,
+            "{} → {}",
+            ops,
+            builder.finish().collect::<String>()
+        )
+        .unwrap();
+    }
+
+    assert_snapshot("hangul_builder", &snapshot);
+}